@@ -1,12 +1,16 @@
 #!/bin/bash
-cargo run --bin client -- --url "http://127.0.0.1:8080/"
+export ZKP_SERVER="http://127.0.0.1:8080"
+export ZKP_USER="${ZKP_USER:-alice}"
+cargo run -q --bin client -- register
+export ZKP_SESSION=$(cargo run -q --bin client -- login --mode exp)
+cargo run -q --bin client -- whoami
+cargo run -q --bin client -- logout
 
 # second option:
 # cargo build --release
-# ./target/release/client
+# ./target/release/client --user alice login
 
 # third option:
 # cargo build --release --bin client
 # cargo install --bin client --path .
-# client --url "http://127.0.0.1:8080/"
-
+# client --server "http://127.0.0.1:8080" --user alice login
@@ -0,0 +1,432 @@
+// Command-line client of the authentication server: register, login, whoami, logout and
+// rotate subcommands, with exit codes per failure type and a JSON output for scripts
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::fmt;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::UNIX_EPOCH;
+use tonic::Code;
+use zkp_protocol_ex::{
+    client::{ClientError, ZkpAuthClient},
+    config::DEFAULT_GROUP,
+    named_groups::NamedGroup,
+    store::AuthMode,
+    tls::ClientTls,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "client",
+    version,
+    about = "Client of the zero-knowledge proof authentication server"
+)]
+struct Cli {
+    /// URL of the server, https for TLS
+    #[arg(
+        long,
+        global = true,
+        alias = "url",
+        env = "ZKP_SERVER",
+        default_value = "http://127.0.0.1:8080"
+    )]
+    server: String,
+    /// Name of the user
+    #[arg(long, short, global = true, env = "ZKP_USER")]
+    user: Option<String>,
+    /// Authentication mode of the login
+    #[arg(long, short, global = true, value_enum, env = "ZKP_MODE", default_value_t = Mode::Exp)]
+    mode: Mode,
+    /// Named group of the exponentiation authentication, the one of the server
+    #[arg(long, global = true, env = "ZKP_GROUP", default_value_t = DEFAULT_GROUP)]
+    group: NamedGroup,
+    /// Print the result, or the error, as a JSON object on the standard output
+    #[arg(long, global = true)]
+    json: bool,
+    /// Read the passwords from the standard input, one per line, instead of prompting
+    #[arg(long, global = true)]
+    password_stdin: bool,
+    /// CA of the server certificate (PEM), required with https
+    #[arg(long, global = true, env = "ZKP_TLS_CA_CERT")]
+    ca_cert: Option<PathBuf>,
+    /// Certificate of the client (PEM) for mutual TLS
+    #[arg(long, global = true, env = "ZKP_TLS_CLIENT_CERT")]
+    cert: Option<PathBuf>,
+    /// Private key of the client certificate (PEM)
+    #[arg(long, global = true, env = "ZKP_TLS_CLIENT_KEY")]
+    key: Option<PathBuf>,
+    /// Name expected in the server certificate, the host of the URL by default
+    #[arg(long, global = true, env = "ZKP_TLS_DOMAIN")]
+    tls_domain: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Register the user with a new password
+    Register,
+    /// Log in and print the session_id
+    Login,
+    /// Print the user and the expiry of a session
+    Whoami {
+        /// Session to check
+        #[arg(long, short, env = "ZKP_SESSION", hide_env_values = true)]
+        session: String,
+    },
+    /// Revoke a session
+    Logout {
+        /// Session to revoke
+        #[arg(long, short, env = "ZKP_SESSION", hide_env_values = true)]
+        session: String,
+        /// Revoke every session of the user
+        #[arg(long)]
+        all: bool,
+    },
+    /// Replace the password of the user, proving the knowledge of the current one
+    Rotate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Chaum-Pedersen over the exponentiation group
+    #[value(alias = "exponentiation")]
+    Exp,
+    /// Chaum-Pedersen over Ristretto
+    #[value(alias = "elliptic-curve-chaum-pedersen")]
+    Ec,
+    /// Opening of the Pedersen commitment over Ristretto
+    #[value(alias = "pedersen-opening")]
+    Pedersen,
+}
+
+impl From<Mode> for AuthMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Exp => AuthMode::Exponentiation,
+            Mode::Ec => AuthMode::EllipticCurveChaumPedersen,
+            Mode::Pedersen => AuthMode::PedersenOpening,
+        }
+    }
+}
+
+///
+/// Exit code of each type of failure, documented in the README
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    Internal = 1,
+    Usage = 2,
+    Connection = 3,
+    Authentication = 4,
+    UnknownUser = 5,
+    UserExists = 6,
+    Rejected = 7,
+}
+
+impl Failure {
+    fn from_code(code: Code) -> Self {
+        match code {
+            Code::Unauthenticated | Code::PermissionDenied => Failure::Authentication,
+            Code::NotFound => Failure::UnknownUser,
+            Code::AlreadyExists => Failure::UserExists,
+            Code::InvalidArgument
+            | Code::FailedPrecondition
+            | Code::OutOfRange
+            | Code::Unimplemented
+            | Code::ResourceExhausted => Failure::Rejected,
+            Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled => Failure::Connection,
+            _ => Failure::Internal,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CliError {
+    failure: Failure,
+    message: String,
+    /// gRPC code and ErrorInfo reason of a status of the server
+    code: Option<Code>,
+    reason: Option<String>,
+}
+
+impl CliError {
+    fn new(failure: Failure, message: impl Into<String>) -> Self {
+        CliError {
+            failure,
+            message: message.into(),
+            code: None,
+            reason: None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "error": {
+                "exit_code": self.failure as u8,
+                "code": self.code.map(|code| format!("{:?}", code)),
+                "reason": self.reason,
+                "message": self.message,
+            }
+        })
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            Some(reason) => write!(f, "{} ({})", self.message, reason),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+impl From<ClientError> for CliError {
+    fn from(e: ClientError) -> Self {
+        let failure = match &e {
+            _ if e.is_unreachable() => Failure::Connection,
+            ClientError::InvalidUrl(_) | ClientError::Tls(_) => Failure::Usage,
+            ClientError::Kdf(_) => Failure::Rejected,
+            ClientError::Status(status) => Failure::from_code(status.code()),
+            _ => Failure::Internal,
+        };
+        CliError {
+            failure,
+            message: e.to_string(),
+            code: e.code(),
+            reason: e.reason(),
+        }
+    }
+}
+
+///
+/// Reads the passwords from the terminal without echo, or line by line from the standard
+/// input with --password-stdin
+///
+struct Passwords {
+    stdin: bool,
+}
+
+impl Passwords {
+    fn read(&self, prompt: &str) -> Result<String, CliError> {
+        if self.stdin {
+            let mut line = String::new();
+            let read = std::io::stdin().lock().read_line(&mut line).map_err(|e| {
+                CliError::new(Failure::Usage, format!("Cannot read the password: {}", e))
+            })?;
+            if read == 0 {
+                return Err(CliError::new(
+                    Failure::Usage,
+                    "Missing password on the standard input",
+                ));
+            }
+            return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        rpassword::prompt_password(prompt)
+            .map_err(|e| CliError::new(Failure::Usage, format!("Cannot read the password: {}", e)))
+    }
+
+    ///
+    /// A new password, typed twice on a terminal
+    ///
+    fn read_new(&self, prompt: &str) -> Result<String, CliError> {
+        let password = self.read(prompt)?;
+        if password.is_empty() {
+            return Err(CliError::new(Failure::Usage, "The password is empty"));
+        }
+        if !self.stdin && self.read("Repeat the password: ")? != password {
+            return Err(CliError::new(Failure::Usage, "The passwords do not match"));
+        }
+        Ok(password)
+    }
+}
+impl Cli {
+    fn user(&self) -> Result<&str, CliError> {
+        self.user.as_deref().ok_or_else(|| {
+            CliError::new(
+                Failure::Usage,
+                "The user is required, use --user or ZKP_USER",
+            )
+        })
+    }
+
+    async fn connect(&self) -> Result<ZkpAuthClient, CliError> {
+        let client = if self.server.starts_with("https://") {
+            let ca_cert = self
+                .ca_cert
+                .clone()
+                .ok_or_else(|| CliError::new(Failure::Usage, "--ca-cert is required with https"))?;
+            let tls = ClientTls {
+                cert: self.cert.clone(),
+                key: self.key.clone(),
+                domain: self.tls_domain.clone(),
+                ..ClientTls::new(ca_cert)
+            };
+            ZkpAuthClient::connect_tls(self.server.clone(), &tls).await?
+        } else {
+            ZkpAuthClient::connect(self.server.clone()).await?
+        };
+        Ok(client.with_group(self.group))
+    }
+}
+
+///
+/// Runs the command, the result is a line for humans and an object for --json
+///
+async fn run(cli: &Cli) -> Result<(String, Value), CliError> {
+    let passwords = Passwords {
+        stdin: cli.password_stdin,
+    };
+    match &cli.command {
+        Command::Register => {
+            let user = cli.user()?;
+            let password = passwords.read_new("Password: ")?;
+            cli.connect().await?.register(user, password).await?;
+            Ok((
+                format!("✅ Registered {}", user),
+                json!({ "user": user, "registered": true }),
+            ))
+        }
+        Command::Login => {
+            let user = cli.user()?;
+            let password = passwords.read("Password: ")?;
+            let session = cli
+                .connect()
+                .await?
+                .login(user, password, cli.mode.into())
+                .await?;
+            let mode = cli
+                .mode
+                .to_possible_value()
+                .map(|value| value.get_name().to_string());
+            Ok((
+                session.id.clone(),
+                json!({ "user": user, "mode": mode, "session_id": session.id }),
+            ))
+        }
+        Command::Whoami { session } => {
+            let validated = cli.connect().await?.validate(session).await?;
+            let expires_at = validated
+                .expires_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs());
+            let mut line = format!("{} (session valid until {})", validated.user, expires_at);
+            if let Some(subject) = &validated.client_subject {
+                line.push_str(&format!(", bound to {}", subject));
+            }
+            Ok((
+                line,
+                json!({
+                    "user": validated.user,
+                    "expires_at": expires_at,
+                    "client_subject": validated.client_subject,
+                    "client_fingerprint": validated.client_fingerprint,
+                }),
+            ))
+        }
+        Command::Logout { session, all } => {
+            let mut client = cli.connect().await?;
+            let line = if *all {
+                client.logout_all(session).await?;
+                "✅ Logged out of every session"
+            } else {
+                client.logout(session).await?;
+                "✅ Logged out"
+            };
+            Ok((
+                line.to_string(),
+                json!({ "logged_out": true, "all_sessions": all }),
+            ))
+        }
+        Command::Rotate => {
+            let user = cli.user()?;
+            let current = passwords.read("Current password: ")?;
+            let password = passwords.read_new("New password: ")?;
+            cli.connect().await?.rotate(user, current, password).await?;
+            Ok((
+                format!("✅ Rotated the credential of {}", user),
+                json!({ "user": user, "rotated": true }),
+            ))
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli).await {
+        Ok((line, value)) => {
+            if cli.json {
+                println!("{}", value);
+            } else {
+                println!("{}", line);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            if cli.json {
+                println!("{}", e.to_json());
+            } else {
+                eprintln!("❌ {}", e);
+            }
+            ExitCode::from(e.failure as u8)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use tonic::Status;
+    use zkp_protocol_ex::error::ZkpError;
+
+    #[test]
+    fn test_arguments() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from([
+            "client", "login", "--user", "alice", "--mode", "ec", "--json",
+        ])
+        .unwrap();
+        assert!(matches!(cli.command, Command::Login));
+        assert_eq!(cli.user.as_deref(), Some("alice"));
+        assert_eq!(cli.mode, Mode::Ec);
+        assert!(cli.json);
+        let cli = Cli::try_parse_from([
+            "client",
+            "--url",
+            "https://auth.example:8443",
+            "--mode",
+            "pedersen-opening",
+            "logout",
+            "--session",
+            "s1",
+            "--all",
+        ])
+        .unwrap();
+        assert_eq!(cli.server, "https://auth.example:8443");
+        assert_eq!(cli.mode, Mode::Pedersen);
+        assert!(matches!(cli.command, Command::Logout { all: true, .. }));
+        assert!(Cli::try_parse_from(["client", "login", "--mode", "rsa"]).is_err());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let error = CliError::from(ClientError::from(Status::from(ZkpError::UnknownUser(
+            "alice".to_string(),
+        ))));
+        assert_eq!(error.failure, Failure::UnknownUser);
+        assert_eq!(error.reason.as_deref(), Some("UNKNOWN_USER"));
+        assert_eq!(error.to_json()["error"]["exit_code"], 5);
+        assert_eq!(error.to_json()["error"]["code"], "NotFound");
+        assert_eq!(
+            Failure::from_code(Code::Unauthenticated),
+            Failure::Authentication
+        );
+        assert_eq!(Failure::from_code(Code::AlreadyExists), Failure::UserExists);
+        assert_eq!(Failure::from_code(Code::Unimplemented), Failure::Rejected);
+        assert_eq!(Failure::from_code(Code::Unavailable), Failure::Connection);
+        assert_eq!(Failure::from_code(Code::Internal), Failure::Internal);
+    }
+}
@@ -0,0 +1,57 @@
+// Generation of deployment specific groups for the exponentiation authentication
+//   groupgen generate <bits_p> <bits_q>   prints the parameters and their certificate (JSON)
+//   groupgen verify <file>                re-verifies the certificate of generated parameters
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::env;
+use zkp_protocol_ex::chaum_pedersen::{GroupCertificate, ZKPProtocol};
+
+#[derive(Serialize, Deserialize)]
+struct GeneratedGroup {
+    p: String,
+    q: String,
+    g: String,
+    h: String,
+    certificate: GroupCertificate,
+}
+
+fn parse_hex(value: &str) -> Result<BigUint, String> {
+    BigUint::parse_bytes(value.as_bytes(), 16).ok_or(format!("Invalid number: {}", value))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("generate") if args.len() == 4 => {
+            let bits_p: u64 = args[2].parse()?;
+            let bits_q: u64 = args[3].parse()?;
+            let (params, certificate) =
+                ZKPProtocol::generate(bits_p, bits_q, &mut rand::rngs::OsRng)?;
+            let group = GeneratedGroup {
+                p: params.p().to_str_radix(16),
+                q: params.q().to_str_radix(16),
+                g: params.g().to_str_radix(16),
+                h: params.h().to_str_radix(16),
+                certificate,
+            };
+            println!("{}", serde_json::to_string_pretty(&group)?);
+        }
+        Some("verify") if args.len() == 3 => {
+            let group: GeneratedGroup = serde_json::from_str(&std::fs::read_to_string(&args[2])?)?;
+            let params = ZKPProtocol::new(
+                parse_hex(&group.p)?,
+                parse_hex(&group.q)?,
+                parse_hex(&group.g)?,
+                parse_hex(&group.h)?,
+            )?;
+            group.certificate.verify(&params)?;
+            println!("✅ Valid certificate");
+        }
+        _ => {
+            eprintln!("Usage: groupgen generate <bits_p> <bits_q> | groupgen verify <file>");
+            std::process::exit(2);
+        }
+    }
+    Ok(())
+}
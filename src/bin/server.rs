@@ -0,0 +1,2232 @@
+use curve25519_dalek::RistrettoPoint;
+use log::{error, info, warn};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tonic::{transport::Server, Response};
+use zkp_protocol_ex::chaum_pedersen::*;
+use zkp_protocol_ex::config::{
+    ServerArgs, ServerConfig, SessionTokenKind, DEFAULT_ATTEMPT_TTL, DEFAULT_SESSION_TIMEOUTS,
+};
+use zkp_protocol_ex::error::ZkpError;
+
+use curve25519_dalek::Scalar;
+use num_bigint::BigUint;
+use zkp_protocol_ex::client::rotation_context;
+use zkp_protocol_ex::group::PrimeOrderGroup;
+use zkp_protocol_ex::kdf::{KdfParams, MIN_SALT_LEN};
+use zkp_protocol_ex::montgomery::ModpScalar;
+use zkp_protocol_ex::named_groups::NamedGroup;
+use zkp_protocol_ex::pedersen_elliptic_curve::{pedersen_setup_base_points, ZKPEllipticCurve};
+use zkp_protocol_ex::sigma;
+use zkp_protocol_ex::store::{
+    AuthAttempt, AuthMode, MemoryUserStore, PublicPair, Session, SessionTimeouts, StoreError,
+    Transcript, UserData, UserStore,
+};
+use zkp_protocol_ex::tls::{peer_certificate, server_tls_config, PeerCertificate};
+use zkp_protocol_ex::token::{Confirmation, SigningKey, TokenClaims, TokenIssuer};
+use zkp_protocol_ex::zkp_auth::{
+    auth_server::{Auth, AuthServer},
+    AuthenticationAnswerRequest, AuthenticationAnswerResponse, AuthenticationChallengeRequest,
+    AuthenticationChallengeResponse, CommitmentOpeningAnswerRequest, CommitmentOpeningResponse,
+    CredentialGroup, KdfParameters, LogoutRequest, LogoutResponse, PedersenCommitmentRequest,
+    PedersenCommitmentResponse, RefreshSessionRequest, RefreshSessionResponse, RegisterRequest,
+    RegisterResponse, RotateCredentialRequest, RotateCredentialResponse, SaltRequest, SaltResponse,
+    ValidateSessionRequest, ValidateSessionResponse,
+};
+
+/// Longest user name accepted
+const MAX_USER_LEN: usize = 256;
+
+pub struct AuthUser {
+    group: NamedGroup,
+    protocol: ZKPProtocol,
+    elliptic_curve: ZKPEllipticCurve,
+    store: Arc<dyn UserStore>,
+    attempt_ttl: Duration,
+    session_timeouts: SessionTimeouts,
+    session_tokens: SessionTokens,
+    modes: Vec<AuthMode>,
+    clock: Arc<dyn Clock>,
+}
+
+///
+/// Form of the session_ids: random strings only meaningful to this server, or tokens
+/// signed by the server that other services verify with its public key
+///
+pub enum SessionTokens {
+    Opaque,
+    Signed {
+        issuer: TokenIssuer,
+        scopes: Vec<String>,
+    },
+}
+
+impl AuthUser {
+    ///
+    /// The parameters of the group are validated before serving any request
+    ///
+    pub fn new(group: NamedGroup, level: &SecurityLevel) -> Result<Self, ParameterError> {
+        let protocol = group.params();
+        protocol.validate(level)?;
+        Ok(AuthUser {
+            group,
+            protocol,
+            elliptic_curve: pedersen_setup_base_points(),
+            store: Arc::new(MemoryUserStore::default()),
+            attempt_ttl: DEFAULT_ATTEMPT_TTL,
+            session_timeouts: DEFAULT_SESSION_TIMEOUTS,
+            session_tokens: SessionTokens::Opaque,
+            modes: AuthMode::ALL.to_vec(),
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    pub fn with_attempt_ttl(mut self, attempt_ttl: Duration) -> Self {
+        self.attempt_ttl = attempt_ttl;
+        self
+    }
+
+    pub fn with_session_timeouts(mut self, session_timeouts: SessionTimeouts) -> Self {
+        self.session_timeouts = session_timeouts;
+        self
+    }
+
+    pub fn with_session_tokens(mut self, session_tokens: SessionTokens) -> Self {
+        self.session_tokens = session_tokens;
+        self
+    }
+
+    ///
+    /// Only the challenges of these modes are created, the others are `Unimplemented`, a
+    /// credential is registered only with a credential of one of them and rotated only
+    /// with a proof of a public pair of one of them
+    ///
+    pub fn with_modes(mut self, modes: Vec<AuthMode>) -> Self {
+        self.modes = modes;
+        self
+    }
+
+    pub fn with_store(mut self, store: Arc<dyn UserStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn check_mode(&self, mode: AuthMode) -> Result<(), ZkpError> {
+        if !self.modes.contains(&mode) {
+            return Err(ZkpError::ModeDisabled(mode));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Remove the attempt of the auth_id: an auth_id is consumed by its first answer,
+    /// valid or not, and cannot be answered after the TTL
+    ///
+    fn take_attempt(&self, auth_id: &str) -> Result<AuthAttempt, ZkpError> {
+        match self.store.take_attempt(auth_id)? {
+            Some(attempt) if !attempt.is_expired(self.clock.now(), self.attempt_ttl) => Ok(attempt),
+            Some(_) => Err(ZkpError::ExpiredChallenge(auth_id.to_string())),
+            None => Err(ZkpError::UnknownChallenge(auth_id.to_string())),
+        }
+    }
+
+    ///
+    /// Evict the expired attempts that were never answered, returns how many were removed
+    ///
+    pub fn sweep_expired_attempts(&self) -> Result<usize, StoreError> {
+        let now = self.clock.now();
+        self.store
+            .retain_attempts(&mut |attempt| !attempt.is_expired(now, self.attempt_ttl))
+    }
+
+    ///
+    /// Evict the expired sessions, returns how many were removed
+    ///
+    pub fn sweep_expired_sessions(&self) -> Result<usize, StoreError> {
+        let now = self.clock.now();
+        self.store
+            .retain_sessions(&mut |session| !session.is_expired(now, &self.session_timeouts))
+    }
+
+    ///
+    /// New session_id for the session: a signed token expires with the absolute timeout
+    /// of the session, the idle timeout is only enforced by this server
+    ///
+    fn new_session_id(&self, session: &Session) -> String {
+        match &self.session_tokens {
+            SessionTokens::Opaque => create_random_string(),
+            SessionTokens::Signed { issuer, scopes } => issuer.issue(&TokenClaims {
+                sub: session.user.clone(),
+                method: session
+                    .mode
+                    .map_or("unknown", |mode| mode.name())
+                    .to_string(),
+                iat: unix_seconds(session.created_at) as u64,
+                exp: unix_seconds(session.created_at + self.session_timeouts.absolute) as u64,
+                scopes: scopes.clone(),
+                jti: create_random_string(),
+                cnf: session
+                    .client_fingerprint
+                    .clone()
+                    .map(|x5t_s256| Confirmation { x5t_s256 }),
+            }),
+        }
+    }
+
+    ///
+    /// Open a new session for the user, a user can have several sessions at once. The
+    /// session is bound to the fingerprint of the client certificate of the login if any,
+    /// and created at the time of the challenge so that a rotation of the credential
+    /// between the challenge and the answer invalidates it
+    ///
+    fn open_session(
+        &self,
+        user: &str,
+        challenged_at: SystemTime,
+        mode: AuthMode,
+        client: Option<PeerCertificate>,
+    ) -> Result<String, ZkpError> {
+        let (client_fingerprint, client_subject) = match client {
+            Some(client) => (Some(client.fingerprint), client.subject),
+            None => (None, None),
+        };
+        let session = Session {
+            created_at: challenged_at,
+            client_subject,
+            client_fingerprint,
+            ..Session::new(user.to_string(), mode, self.clock.now())
+        };
+        let session_id = self.new_session_id(&session);
+        self.store.insert_session(&session_id, &session)?;
+        Ok(session_id)
+    }
+
+    ///
+    /// Session of the session_id if it has not expired: a validation restarts its idle
+    /// timeout, a refresh or a logout removes it from the store but only for the client
+    /// of the login. A session bound to a client certificate is only validated for that
+    /// client. An expired session, or one whose credential was rotated since the login,
+    /// is always removed
+    ///
+    fn use_session(&self, session_id: &str, usage: SessionUse) -> Result<Session, ZkpError> {
+        let now = self.clock.now();
+        let take = matches!(usage, SessionUse::Take { .. });
+        let session = self.store.modify_session(session_id, |entry| {
+            let session = entry.take()?;
+            if session.is_expired(now, &self.session_timeouts) {
+                return None;
+            }
+            let bound = session.client_fingerprint.as_deref();
+            let allowed = match usage {
+                SessionUse::Validate { client_fingerprint } => {
+                    bound.is_none() || bound == client_fingerprint
+                }
+                SessionUse::Take { client_fingerprint } => bound == client_fingerprint,
+            };
+            if !allowed {
+                *entry = Some(session);
+                return None;
+            }
+            let session = Session {
+                last_seen: now,
+                ..session
+            };
+            if !take {
+                *entry = Some(session.clone());
+            }
+            Some(session)
+        })?;
+        let session = session.ok_or(ZkpError::InvalidSession)?;
+        // the sessions of the previous credential are revoked after its replacement, a
+        // login racing with the rotation is caught here
+        let valid = self
+            .store
+            .lookup_user(&session.user)?
+            .is_some_and(|user_data| !user_data.rotated_since(session.created_at));
+        if !valid {
+            self.store
+                .modify_session(session_id, |entry| *entry = None)?;
+            return Err(ZkpError::InvalidSession);
+        }
+        Ok(session)
+    }
+}
+
+///
+/// Use of a session by `use_session`: any service validates it for the client presenting
+/// the certificate of the login, only that client takes it to refresh or revoke it. A
+/// session of a login without certificate is validated for any client
+///
+enum SessionUse<'a> {
+    Validate { client_fingerprint: Option<&'a str> },
+    Take { client_fingerprint: Option<&'a str> },
+}
+
+///
+/// Source of the current time, replaced by a fake clock in the tests
+///
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+///
+/// Background task evicting the expired attempts and sessions, it stops when the server
+/// is dropped
+///
+pub fn spawn_sweeper(auth_user: &Arc<AuthUser>, period: Duration) -> JoinHandle<()> {
+    let auth_user = Arc::downgrade(auth_user);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let Some(auth_user) = auth_user.upgrade() else {
+                return;
+            };
+            match auth_user.sweep_expired_attempts() {
+                Ok(0) => {}
+                Ok(evicted) => info!("Evicted {} expired authentication attempts", evicted),
+                Err(e) => error!("Could not evict the expired attempts: {}", e),
+            }
+            match auth_user.sweep_expired_sessions() {
+                Ok(0) => {}
+                Ok(evicted) => info!("Evicted {} expired sessions", evicted),
+                Err(e) => error!("Could not evict the expired sessions: {}", e),
+            }
+        }
+    })
+}
+
+///
+/// Group of a Chaum-Pedersen authentication mode: where the public pair of a user is
+/// registered and how the attempts keep the commitment and the challenge
+///
+trait ChaumPedersenMode: PrimeOrderGroup + Sized {
+    const MODE: AuthMode;
+    const CREDENTIAL_GROUP: CredentialGroup;
+    const PUBLIC_PAIR: &'static str;
+    fn public_pair(user_data: &UserData) -> Option<&PublicPair<Self>>;
+    fn transcript(&self, r1: Self::Element, r2: Self::Element, c: Self::Scalar) -> Transcript;
+    fn read_transcript(
+        &self,
+        transcript: &Transcript,
+    ) -> Option<(Self::Element, Self::Element, Self::Scalar)>;
+    fn verify_non_interactive_proof(
+        &self,
+        c: &Self::Scalar,
+        s: &Self::Scalar,
+        y1: &Self::Element,
+        y2: &Self::Element,
+        context: &[u8],
+    ) -> bool;
+}
+
+impl ChaumPedersenMode for ZKPProtocol {
+    const MODE: AuthMode = AuthMode::Exponentiation;
+    const CREDENTIAL_GROUP: CredentialGroup = CredentialGroup::Exponentiation;
+    const PUBLIC_PAIR: &'static str = "exponentiation public pair";
+
+    fn public_pair(user_data: &UserData) -> Option<&PublicPair<Self>> {
+        user_data.exponentiation.as_ref()
+    }
+
+    fn transcript(&self, r1: BigUint, r2: BigUint, c: ModpScalar) -> Transcript {
+        Transcript::Exponentiation {
+            r1,
+            r2,
+            c: c.to_biguint(),
+        }
+    }
+
+    fn read_transcript(&self, transcript: &Transcript) -> Option<(BigUint, BigUint, ModpScalar)> {
+        match transcript {
+            Transcript::Exponentiation { r1, r2, c } => {
+                Some((r1.clone(), r2.clone(), self.scalar_from_biguint(c)))
+            }
+            _ => None,
+        }
+    }
+
+    fn verify_non_interactive_proof(
+        &self,
+        c: &ModpScalar,
+        s: &ModpScalar,
+        y1: &BigUint,
+        y2: &BigUint,
+        context: &[u8],
+    ) -> bool {
+        let proof = Proof {
+            c: c.to_biguint(),
+            s: s.to_biguint(),
+        };
+        self.verify_non_interactive(&proof, y1, y2, context)
+    }
+}
+
+impl ChaumPedersenMode for ZKPEllipticCurve {
+    const MODE: AuthMode = AuthMode::EllipticCurveChaumPedersen;
+    const CREDENTIAL_GROUP: CredentialGroup = CredentialGroup::EllipticCurve;
+    const PUBLIC_PAIR: &'static str = "elliptic curve public pair";
+
+    fn public_pair(user_data: &UserData) -> Option<&PublicPair<Self>> {
+        user_data.elliptic_curve.as_ref()
+    }
+
+    fn transcript(&self, r1: RistrettoPoint, r2: RistrettoPoint, c: Scalar) -> Transcript {
+        Transcript::EllipticCurveChaumPedersen { r1, r2, c }
+    }
+
+    fn read_transcript(
+        &self,
+        transcript: &Transcript,
+    ) -> Option<(RistrettoPoint, RistrettoPoint, Scalar)> {
+        match transcript {
+            Transcript::EllipticCurveChaumPedersen { r1, r2, c } => Some((*r1, *r2, *c)),
+            _ => None,
+        }
+    }
+
+    fn verify_non_interactive_proof(
+        &self,
+        c: &Scalar,
+        s: &Scalar,
+        y1: &RistrettoPoint,
+        y2: &RistrettoPoint,
+        context: &[u8],
+    ) -> bool {
+        self.verify_non_interactive(c, s, y1, y2, context)
+    }
+}
+
+impl AuthUser {
+    ///
+    /// Public pair of the user in the group of the mode, an exponentiation pair registered
+    /// in another group than the one of the server is as good as missing
+    ///
+    fn registered_pair<'a, G: ChaumPedersenMode>(
+        &self,
+        user_data: &'a UserData,
+    ) -> Result<&'a PublicPair<G>, ZkpError> {
+        let missing = || ZkpError::MissingCredential {
+            user: user_data.user.clone(),
+            credential: G::PUBLIC_PAIR,
+        };
+        let pair = G::public_pair(user_data).ok_or_else(missing)?;
+        if G::MODE == AuthMode::Exponentiation {
+            if let Some(group) = &user_data.exponentiation_group {
+                if group != self.group.name() {
+                    warn!(
+                        "User {} registered its exponentiation pair in group {}, not {}",
+                        user_data.user, group, self.group
+                    );
+                    return Err(missing());
+                }
+            }
+        }
+        Ok(pair)
+    }
+
+    ///
+    /// Record of a new user from its credential, the public pairs are checked
+    ///
+    fn decode_credential(&self, credential: RegisterRequest) -> Result<UserData, ZkpError> {
+        check_user(&credential.user)?;
+        let kdf_params = credential.kdf_parameters.map(|params| KdfParams {
+            memory_kib: params.memory_kib,
+            iterations: params.iterations,
+            parallelism: params.parallelism,
+        });
+        match (&kdf_params, credential.salt.len()) {
+            (None, 0) => {}
+            (Some(params), len) if len >= MIN_SALT_LEN => {
+                params
+                    .validate()
+                    .map_err(|e| ZkpError::invalid_argument("kdf_parameters", e.to_string()))?;
+            }
+            (Some(_), len) => {
+                return Err(ZkpError::invalid_argument(
+                    "salt",
+                    format!("{} bytes, at least {} are required", len, MIN_SALT_LEN),
+                ))
+            }
+            (None, _) => {
+                return Err(ZkpError::invalid_argument(
+                    "kdf_parameters",
+                    "required with the salt",
+                ))
+            }
+        }
+        let mut user_data = UserData {
+            user: credential.user,
+            salt: credential.salt,
+            kdf_params,
+            rotation_nonce: thread_rng().gen::<[u8; 16]>().to_vec(),
+            ..Default::default()
+        };
+        user_data.exponentiation =
+            decode_public_pair(&self.protocol, ["y1", "y2"], &credential.y1, &credential.y2)?;
+        if user_data.exponentiation.is_some() {
+            user_data.exponentiation_group = Some(self.group.name().to_string());
+        }
+        user_data.elliptic_curve = decode_public_pair(
+            &self.elliptic_curve,
+            ["ec_y1", "ec_y2"],
+            &credential.ec_y1,
+            &credential.ec_y2,
+        )?;
+        if !credential.pedersen_commitment.is_empty() {
+            user_data.pedersen_commitment = Some(decode_element(
+                &self.elliptic_curve,
+                "pedersen_commitment",
+                &credential.pedersen_commitment,
+            )?);
+            if credential.blinding_salt.len() < MIN_SALT_LEN {
+                return Err(ZkpError::invalid_argument(
+                    "blinding_salt",
+                    format!(
+                        "{} bytes, at least {} are required",
+                        credential.blinding_salt.len(),
+                        MIN_SALT_LEN
+                    ),
+                ));
+            }
+            user_data.blinding_salt = credential.blinding_salt;
+        }
+        if user_data.exponentiation.is_none()
+            && user_data.elliptic_curve.is_none()
+            && user_data.pedersen_commitment.is_none()
+        {
+            return Err(ZkpError::invalid_argument(
+                "credential",
+                "no public pair nor pedersen_commitment",
+            ));
+        }
+        // a user must be able to log in with one of the enabled modes
+        let usable = self.modes.iter().any(|mode| match mode {
+            AuthMode::Exponentiation => user_data.exponentiation.is_some(),
+            AuthMode::EllipticCurveChaumPedersen => user_data.elliptic_curve.is_some(),
+            AuthMode::PedersenOpening => user_data.pedersen_commitment.is_some(),
+        });
+        if !usable {
+            return Err(ZkpError::invalid_argument(
+                "credential",
+                "no credential of an enabled authentication mode",
+            ));
+        }
+        Ok(user_data)
+    }
+
+    ///
+    /// Replaces the registered record by the new credential if (c, s) proves the knowledge
+    /// of the secret of the public pair registered in the group, all in one update of the
+    /// store
+    ///
+    fn rotate_credential_with<G: ChaumPedersenMode>(
+        &self,
+        group: &G,
+        credential: RegisterRequest,
+        c: &[u8],
+        s: &[u8],
+    ) -> Result<(), ZkpError> {
+        self.check_mode(G::MODE)?;
+        let c = decode_scalar(group, "c", c)?;
+        let s = decode_scalar(group, "s", s)?;
+        let user = credential.user.clone();
+        let mut user_data = self.decode_credential(credential.clone())?;
+        self.store.modify_user(&user, |entry| {
+            let Some(registered) = entry else {
+                return Err(ZkpError::UnknownUser(user.clone()));
+            };
+            let (y1, y2) = self.registered_pair::<G>(registered)?;
+            let context =
+                rotation_context(&credential, G::CREDENTIAL_GROUP, &registered.rotation_nonce);
+            if !group.verify_non_interactive_proof(&c, &s, y1, y2, &context) {
+                return Err(ZkpError::VerificationFailed { user: user.clone() });
+            }
+            // read within the update: a login that saw the previous credential was
+            // challenged before this time
+            user_data.rotated_at = Some(self.clock.now());
+            *entry = Some(user_data);
+            Ok(())
+        })?
+    }
+
+    ///
+    /// Chaum-Pedersen challenge over the group of the mode, a new attempt is recorded
+    ///
+    fn create_chaum_pedersen_challenge<G: ChaumPedersenMode>(
+        &self,
+        group: &G,
+        request: AuthenticationChallengeRequest,
+    ) -> Result<AuthenticationChallengeResponse, ZkpError> {
+        self.check_mode(G::MODE)?;
+        let user = request.user;
+        check_user(&user)?;
+        let r1 = decode_element(group, "r1", &request.r1)?;
+        let r2 = decode_element(group, "r2", &request.r2)?;
+        let Some(user_data) = self.store.lookup_user(&user)? else {
+            return Err(ZkpError::UnknownUser(user));
+        };
+        self.registered_pair::<G>(&user_data)?;
+        let challenge = group.random_scalar();
+        let c = group.encode_scalar(&challenge);
+        let auth_id = create_random_string();
+        self.store.insert_attempt(
+            &auth_id,
+            &AuthAttempt::new(user, self.clock.now(), group.transcript(r1, r2, challenge)),
+        )?;
+        Ok(AuthenticationChallengeResponse { auth_id, c })
+    }
+
+    ///
+    /// Chaum-Pedersen verification against the attempt of the auth_id only,
+    /// a session is opened if the solution is valid
+    ///
+    fn verify_chaum_pedersen_solution<G: ChaumPedersenMode>(
+        &self,
+        group: &G,
+        request: AuthenticationAnswerRequest,
+        client: Option<PeerCertificate>,
+    ) -> Result<AuthenticationAnswerResponse, ZkpError> {
+        let auth_id = request.auth_id;
+        let attempt = self.take_attempt(&auth_id)?;
+        let s = decode_scalar(group, "s", &request.s)?;
+        let Some((r1, r2, c)) = group.read_transcript(&attempt.transcript) else {
+            return Err(ZkpError::ChallengeModeMismatch {
+                auth_id,
+                expected: G::MODE,
+                found: attempt.mode(),
+            });
+        };
+        let u = &attempt.user;
+        let Some(user_data) = self.store.lookup_user(u)? else {
+            return Err(ZkpError::UnknownUser(u.clone()));
+        };
+        info!(
+            "User {} found for auth_id {}, challenge sent {:?} ago",
+            user_data.user,
+            auth_id,
+            self.clock
+                .now()
+                .duration_since(attempt.created_at)
+                .unwrap_or_default()
+        );
+        if user_data.rotated_since(attempt.created_at) {
+            return Err(ZkpError::ExpiredChallenge(auth_id));
+        }
+        let (y1, y2) = self.registered_pair::<G>(&user_data)?;
+        if !sigma::chaum_pedersen::verify(group, &c, &s, &r1, &r2, y1, y2) {
+            return Err(ZkpError::VerificationFailed { user: u.clone() });
+        }
+        let session_id = self.open_session(u, attempt.created_at, G::MODE, client)?;
+        Ok(AuthenticationAnswerResponse { session_id })
+    }
+}
+
+#[tonic::async_trait]
+impl Auth for AuthUser {
+    async fn register(
+        &self,
+        request: tonic::Request<RegisterRequest>,
+    ) -> std::result::Result<tonic::Response<RegisterResponse>, tonic::Status> {
+        let req_data = request.into_inner();
+        let user = req_data.user.clone();
+        info!("Register user {}", user);
+        let user_data = self.decode_credential(req_data)?;
+        let inserted = self
+            .store
+            .modify_user(&user, |entry| {
+                if entry.is_some() {
+                    return false;
+                }
+                *entry = Some(user_data);
+                true
+            })
+            .map_err(ZkpError::from)?;
+        if !inserted {
+            return Err(ZkpError::UserExists(user).into());
+        }
+
+        Ok(Response::new(RegisterResponse {}))
+    }
+
+    async fn rotate_credential(
+        &self,
+        request: tonic::Request<RotateCredentialRequest>,
+    ) -> std::result::Result<tonic::Response<RotateCredentialResponse>, tonic::Status> {
+        let req_data = request.into_inner();
+        let Some(credential) = req_data.credential else {
+            return Err(ZkpError::invalid_argument("credential", "missing").into());
+        };
+        let user = credential.user.clone();
+        info!("Rotate the credential of user {}", user);
+        match CredentialGroup::try_from(req_data.group) {
+            Ok(CredentialGroup::Exponentiation) => {
+                self.rotate_credential_with(&self.protocol, credential, &req_data.c, &req_data.s)?
+            }
+            Ok(CredentialGroup::EllipticCurve) => self.rotate_credential_with(
+                &self.elliptic_curve,
+                credential,
+                &req_data.c,
+                &req_data.s,
+            )?,
+            Err(_) => {
+                return Err(ZkpError::invalid_argument(
+                    "group",
+                    format!("unknown credential group {}", req_data.group),
+                )
+                .into())
+            }
+        }
+        // the sessions opened with the previous secret are already rejected by the time of
+        // the rotation, they are removed from the store
+        let revoked = self
+            .store
+            .remove_user_sessions(&user)
+            .map_err(ZkpError::from)?;
+        info!(
+            "Rotate the credential of user {}: credential replaced, {} sessions revoked",
+            user, revoked
+        );
+        Ok(Response::new(RotateCredentialResponse {}))
+    }
+
+    async fn get_salt(
+        &self,
+        request: tonic::Request<SaltRequest>,
+    ) -> std::result::Result<tonic::Response<SaltResponse>, tonic::Status> {
+        let user = request.into_inner().user;
+        check_user(&user)?;
+        info!("Get the salt of user {}", user);
+        let Some(user_data) = self.store.lookup_user(&user).map_err(ZkpError::from)? else {
+            return Err(ZkpError::UnknownUser(user).into());
+        };
+        let Some(params) = user_data.kdf_params.filter(|_| !user_data.salt.is_empty()) else {
+            return Err(ZkpError::MissingCredential {
+                user,
+                credential: "password salt",
+            }
+            .into());
+        };
+        Ok(Response::new(SaltResponse {
+            salt: user_data.salt,
+            blinding_salt: user_data.blinding_salt,
+            rotation_nonce: user_data.rotation_nonce,
+            kdf_parameters: Some(KdfParameters {
+                memory_kib: params.memory_kib,
+                iterations: params.iterations,
+                parallelism: params.parallelism,
+            }),
+        }))
+    }
+
+    async fn create_authentication_challenge(
+        &self,
+        request: tonic::Request<AuthenticationChallengeRequest>,
+    ) -> std::result::Result<tonic::Response<AuthenticationChallengeResponse>, tonic::Status> {
+        let req_data = request.into_inner();
+        info!(
+            "Exponentiation auth: create authentication challenge for user {}",
+            req_data.user
+        );
+        let response = self.create_chaum_pedersen_challenge(&self.protocol, req_data)?;
+        Ok(Response::new(response))
+    }
+
+    async fn verify_authentication(
+        &self,
+        request: tonic::Request<AuthenticationAnswerRequest>,
+    ) -> std::result::Result<tonic::Response<AuthenticationAnswerResponse>, tonic::Status> {
+        let client = peer_certificate(&request);
+        let req = request.into_inner();
+        info!(
+            "Exponentiation auth: verify authentication for auth_id {}",
+            req.auth_id
+        );
+        let response = self.verify_chaum_pedersen_solution(&self.protocol, req, client)?;
+        Ok(Response::new(response))
+    }
+
+    async fn create_elliptic_curve_authentication_challenge(
+        &self,
+        request: tonic::Request<AuthenticationChallengeRequest>,
+    ) -> std::result::Result<tonic::Response<AuthenticationChallengeResponse>, tonic::Status> {
+        let req_data = request.into_inner();
+        info!(
+            "Elliptic curve Chaum-Pedersen auth: create authentication challenge for user {}",
+            req_data.user
+        );
+        let response = self.create_chaum_pedersen_challenge(&self.elliptic_curve, req_data)?;
+        Ok(Response::new(response))
+    }
+
+    async fn verify_elliptic_curve_authentication(
+        &self,
+        request: tonic::Request<AuthenticationAnswerRequest>,
+    ) -> std::result::Result<tonic::Response<AuthenticationAnswerResponse>, tonic::Status> {
+        let client = peer_certificate(&request);
+        let req = request.into_inner();
+        info!(
+            "Elliptic curve Chaum-Pedersen auth: verify authentication for auth_id {}",
+            req.auth_id
+        );
+        let response = self.verify_chaum_pedersen_solution(&self.elliptic_curve, req, client)?;
+        Ok(Response::new(response))
+    }
+
+    async fn send_pedersen_commitment(
+        &self,
+        request: tonic::Request<PedersenCommitmentRequest>,
+    ) -> std::result::Result<tonic::Response<PedersenCommitmentResponse>, tonic::Status> {
+        let req_data = request.into_inner();
+        let user = req_data.user;
+        info!(
+            "Elliptic curve auth: user {} sends pedersen commitment",
+            user
+        );
+        self.check_mode(AuthMode::PedersenOpening)?;
+        check_user(&user)?;
+        let t = decode_element(&self.elliptic_curve, "t", &req_data.t)?;
+        let Some(user_data) = self.store.lookup_user(&user).map_err(ZkpError::from)? else {
+            return Err(ZkpError::UnknownUser(user).into());
+        };
+        if user_data.pedersen_commitment.is_none() {
+            return Err(ZkpError::MissingCredential {
+                user,
+                credential: "pedersen commitment",
+            }
+            .into());
+        }
+        let challenge = self.elliptic_curve.random_scalar();
+        let auth_id = create_random_string();
+        self.store
+            .insert_attempt(
+                &auth_id,
+                &AuthAttempt::new(
+                    user,
+                    self.clock.now(),
+                    Transcript::PedersenOpening { t, c: challenge },
+                ),
+            )
+            .map_err(ZkpError::from)?;
+        Ok(Response::new(PedersenCommitmentResponse {
+            auth_id,
+            c: challenge.to_bytes().to_vec(),
+        }))
+    }
+
+    async fn open_commitment(
+        &self,
+        request: tonic::Request<CommitmentOpeningAnswerRequest>,
+    ) -> std::result::Result<tonic::Response<CommitmentOpeningResponse>, tonic::Status> {
+        let client = peer_certificate(&request);
+        let req = request.into_inner();
+        let auth_id = req.auth_id;
+        info!(
+            "Elliptic curve auth: user with auth_id {} proves the commitment opening",
+            auth_id
+        );
+        let attempt = self.take_attempt(&auth_id)?;
+        let s1 = decode_scalar(&self.elliptic_curve, "s1", &req.s1)?;
+        let s2 = decode_scalar(&self.elliptic_curve, "s2", &req.s2)?;
+        let Transcript::PedersenOpening { t, c } = attempt.transcript else {
+            return Err(ZkpError::ChallengeModeMismatch {
+                auth_id,
+                expected: AuthMode::PedersenOpening,
+                found: attempt.mode(),
+            }
+            .into());
+        };
+        let u = &attempt.user;
+        let Some(user_data) = self.store.lookup_user(u).map_err(ZkpError::from)? else {
+            return Err(ZkpError::UnknownUser(u.clone()).into());
+        };
+        info!(
+            "Elliptic auth: user {} found for auth_id {}",
+            user_data.user, auth_id
+        );
+        if user_data.rotated_since(attempt.created_at) {
+            return Err(ZkpError::ExpiredChallenge(auth_id).into());
+        }
+        let Some(commitment) = user_data.pedersen_commitment else {
+            return Err(ZkpError::MissingCredential {
+                user: u.clone(),
+                credential: "pedersen commitment",
+            }
+            .into());
+        };
+        let verified = self
+            .elliptic_curve
+            .verify_opening(&c, &s1, &s2, &t, &commitment);
+        if !verified {
+            return Err(ZkpError::VerificationFailed { user: u.clone() }.into());
+        }
+        let session_id =
+            self.open_session(u, attempt.created_at, AuthMode::PedersenOpening, client)?;
+        Ok(Response::new(CommitmentOpeningResponse { session_id }))
+    }
+
+    async fn validate_session(
+        &self,
+        request: tonic::Request<ValidateSessionRequest>,
+    ) -> std::result::Result<tonic::Response<ValidateSessionResponse>, tonic::Status> {
+        let caller = peer_certificate(&request);
+        let req = request.into_inner();
+        // a service validating the session of its client sends the certificate of that client
+        let client_fingerprint = match req.client_fingerprint {
+            fingerprint if fingerprint.is_empty() => caller.map(|caller| caller.fingerprint),
+            fingerprint => Some(fingerprint),
+        };
+        let session = self.use_session(
+            &req.session_id,
+            SessionUse::Validate {
+                client_fingerprint: client_fingerprint.as_deref(),
+            },
+        )?;
+        info!("Session of user {} validated", session.user);
+        Ok(Response::new(ValidateSessionResponse {
+            expires_at: unix_seconds(session.expires_at(&self.session_timeouts)),
+            user: session.user,
+            client_subject: session.client_subject.unwrap_or_default(),
+            client_fingerprint: session.client_fingerprint.unwrap_or_default(),
+        }))
+    }
+
+    async fn refresh_session(
+        &self,
+        request: tonic::Request<RefreshSessionRequest>,
+    ) -> std::result::Result<tonic::Response<RefreshSessionResponse>, tonic::Status> {
+        let client = peer_certificate(&request);
+        let session = self.use_session(
+            &request.into_inner().session_id,
+            SessionUse::Take {
+                client_fingerprint: client.as_ref().map(|client| client.fingerprint.as_str()),
+            },
+        )?;
+        info!("Session of user {} refreshed", session.user);
+        let session_id = self.new_session_id(&session);
+        self.store
+            .insert_session(&session_id, &session)
+            .map_err(ZkpError::from)?;
+        Ok(Response::new(RefreshSessionResponse {
+            session_id,
+            expires_at: unix_seconds(session.expires_at(&self.session_timeouts)),
+        }))
+    }
+
+    async fn logout(
+        &self,
+        request: tonic::Request<LogoutRequest>,
+    ) -> std::result::Result<tonic::Response<LogoutResponse>, tonic::Status> {
+        let client = peer_certificate(&request);
+        let req = request.into_inner();
+        let session = self.use_session(
+            &req.session_id,
+            SessionUse::Take {
+                client_fingerprint: client.as_ref().map(|client| client.fingerprint.as_str()),
+            },
+        )?;
+        if req.all_sessions {
+            let revoked = self
+                .store
+                .remove_user_sessions(&session.user)
+                .map_err(ZkpError::from)?;
+            info!(
+                "User {} logged out of {} other sessions",
+                session.user, revoked
+            );
+        }
+        info!("User {} logged out", session.user);
+        Ok(Response::new(LogoutResponse {}))
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+pub fn create_random_string() -> String {
+    let rand_string: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(30)
+        .map(char::from)
+        .collect();
+    rand_string
+}
+
+///
+/// The user names are non-empty and at most MAX_USER_LEN bytes
+///
+fn check_user(user: &str) -> Result<(), ZkpError> {
+    if user.is_empty() {
+        return Err(ZkpError::invalid_argument("user", "empty"));
+    }
+    if user.len() > MAX_USER_LEN {
+        return Err(ZkpError::invalid_argument(
+            "user",
+            format!("{} bytes, at most {} are allowed", user.len(), MAX_USER_LEN),
+        ));
+    }
+    Ok(())
+}
+
+///
+/// Element of the group sent in the request field `field`, rejected with the reason if it
+/// is not the canonical encoding of an element of the subgroup other than the identity
+///
+fn decode_element<G: PrimeOrderGroup>(
+    group: &G,
+    field: &str,
+    bytes: &[u8],
+) -> Result<G::Element, ZkpError> {
+    group
+        .decode_element(bytes)
+        .map_err(|e| ZkpError::decoding(field, e))
+}
+
+fn decode_scalar<G: PrimeOrderGroup>(
+    group: &G,
+    field: &str,
+    bytes: &[u8],
+) -> Result<G::Scalar, ZkpError> {
+    group
+        .decode_scalar(bytes)
+        .map_err(|e| ZkpError::decoding(field, e))
+}
+
+///
+/// The public pair of a group is optional, None when both values are empty
+///
+fn decode_public_pair<G: PrimeOrderGroup>(
+    group: &G,
+    [field1, field2]: [&str; 2],
+    y1: &[u8],
+    y2: &[u8],
+) -> Result<Option<PublicPair<G>>, ZkpError> {
+    if y1.is_empty() && y2.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((
+        decode_element(group, field1, y1)?,
+        decode_element(group, field2, y2)?,
+    )))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = ServerArgs::parse_with_env();
+    let config = ServerConfig::load(&args).map_err(|e| e.to_string())?;
+    if args.print_config {
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+    env_logger::Builder::new()
+        .parse_filters(&config.log_level)
+        .init();
+    info!("Running the server on {}", config.listen_address());
+    let modes: Vec<&str> = config.modes.iter().map(AuthMode::name).collect();
+    info!(
+        "Group {}, authentication modes {}",
+        config.group,
+        modes.join(", ")
+    );
+    info!("Users stored in {}", config.store);
+    let session_tokens = match config.session_tokens {
+        SessionTokenKind::Opaque => SessionTokens::Opaque,
+        SessionTokenKind::Signed => {
+            let key = match config.token_signing_key()? {
+                Some(key) => key,
+                None => SigningKey::generate(&create_random_string())?,
+            };
+            let issuer = TokenIssuer::new(key);
+            info!(
+                "Session tokens signed, public key {}",
+                issuer.verifying_key()
+            );
+            SessionTokens::Signed {
+                issuer,
+                scopes: Vec::new(),
+            }
+        }
+    };
+    let auth_user = Arc::new(
+        AuthUser::new(config.group, &config.security_level()?)
+            .map_err(|e| format!("Invalid group parameters: {}", e))?
+            .with_store(config.store.open()?)
+            .with_modes(config.modes.clone())
+            .with_attempt_ttl(config.attempt_ttl())
+            .with_session_timeouts(config.session_timeouts())
+            .with_session_tokens(session_tokens),
+    );
+    spawn_sweeper(&auth_user, config.sweep_period());
+    let mut server = Server::builder();
+    if let Some(tls) = server_tls_config(&config.tls).map_err(|e| e.to_string())? {
+        info!(
+            "TLS enabled, client certificates {}",
+            if config.tls.client_ca.is_some() {
+                "required"
+            } else {
+                "not requested"
+            }
+        );
+        server = server
+            .tls_config(tls)
+            .map_err(|e| format!("Invalid TLS configuration: {}", e))?;
+    }
+    server
+        .add_service(AuthServer::from_arc(auth_user))
+        .serve(config.listen_address())
+        .await
+        .map_err(|e| format!("Could not start the server: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::Request;
+    use tonic::{Code, Status};
+    use tower::{Layer, Service};
+    use zkp_protocol_ex::config::TlsConfig;
+    use zkp_protocol_ex::error::error_info;
+    use zkp_protocol_ex::pedersen_elliptic_curve::{
+        derive_blinding_factor, generate_random_scalar,
+    };
+    use zkp_protocol_ex::session_auth::{
+        AuthenticatedUser, SessionAuthLayer, AUTHORIZATION_HEADER,
+    };
+    use zkp_protocol_ex::store::SqliteUserStore;
+    use zkp_protocol_ex::tls::ClientTls;
+    use zkp_protocol_ex::token::TokenVerifier;
+    use zkp_protocol_ex::zkp_auth::auth_client::AuthClient;
+
+    const USER: &str = "alice";
+    const BLINDING_SALT: [u8; 16] = [7; 16];
+    const SALT: [u8; 16] = [5; 16];
+
+    fn auth_user() -> AuthUser {
+        AuthUser::new(NamedGroup::Rfc5114Modp2048Q224, &SecurityLevel::BITS_112).unwrap()
+    }
+
+    fn credential(auth: &AuthUser, x: &ModpScalar, ec_x: &Scalar) -> RegisterRequest {
+        let (y1, y2) = auth.protocol.compute_public_pair(x);
+        let (ec_y1, ec_y2) = auth.elliptic_curve.compute_public_pair(ec_x);
+        let commitment = auth
+            .elliptic_curve
+            .pedersen_commit(*ec_x, derive_blinding_factor(ec_x, &BLINDING_SALT));
+        RegisterRequest {
+            user: USER.to_string(),
+            y1: y1.to_bytes_be(),
+            y2: y2.to_bytes_be(),
+            ec_y1: ec_y1.compress().to_bytes().to_vec(),
+            ec_y2: ec_y2.compress().to_bytes().to_vec(),
+            pedersen_commitment: commitment.compress().to_bytes().to_vec(),
+            blinding_salt: BLINDING_SALT.to_vec(),
+            salt: SALT.to_vec(),
+            kdf_parameters: Some(KdfParameters {
+                memory_kib: KdfParams::INSECURE_FAST.memory_kib,
+                iterations: KdfParams::INSECURE_FAST.iterations,
+                parallelism: KdfParams::INSECURE_FAST.parallelism,
+            }),
+        }
+    }
+
+    async fn register(auth: &AuthUser, x: &ModpScalar, ec_x: &Scalar) {
+        let request = credential(auth, x, ec_x);
+        auth.register(Request::new(request)).await.unwrap();
+    }
+
+    fn rotation_nonce(auth: &AuthUser, user: &str) -> Vec<u8> {
+        auth.store
+            .lookup_user(user)
+            .unwrap()
+            .unwrap()
+            .rotation_nonce
+    }
+
+    fn scalar(auth: &AuthUser, value: u32) -> ModpScalar {
+        auth.protocol.scalar_from_biguint(&BigUint::from(value))
+    }
+
+    ///
+    /// Exponentiation challenge for the nonce k, returns the auth_id and c
+    ///
+    async fn challenge(auth: &AuthUser, k: &ModpScalar) -> (String, ModpScalar) {
+        let (r1, r2) = auth.protocol.compute_public_pair(k);
+        let response = auth
+            .create_authentication_challenge(Request::new(AuthenticationChallengeRequest {
+                user: USER.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        (
+            response.auth_id,
+            auth.protocol.decode_scalar(&response.c).unwrap(),
+        )
+    }
+
+    async fn answer(
+        auth: &AuthUser,
+        auth_id: String,
+        s: &ModpScalar,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        auth.verify_authentication(Request::new(AuthenticationAnswerRequest {
+            auth_id,
+            s: auth.protocol.encode_scalar(s),
+        }))
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_parallel_logins() {
+        let auth = auth_user();
+        let x = auth.protocol.random_scalar();
+        register(&auth, &x, &generate_random_scalar()).await;
+
+        // two devices get a challenge before any of them answers
+        let k1 = auth.protocol.random_scalar();
+        let k2 = auth.protocol.random_scalar();
+        let (auth_id1, c1) = challenge(&auth, &k1).await;
+        let (auth_id2, c2) = challenge(&auth, &k2).await;
+        assert_ne!(auth_id1, auth_id2);
+
+        let s2 = auth.protocol.compute_solution_for_challenge(&k2, &c2, &x);
+        assert!(answer(&auth, auth_id2, &s2).await.is_ok());
+        let s1 = auth.protocol.compute_solution_for_challenge(&k1, &c1, &x);
+        assert!(answer(&auth, auth_id1.clone(), &s1).await.is_ok());
+
+        // a solution is only valid for the challenge of its own attempt
+        let (auth_id3, c3) = challenge(&auth, &k1).await;
+        assert_ne!(c3, c1);
+        let status = answer(&auth, auth_id3, &s1).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_attempt_mode_mismatch() {
+        let auth = auth_user();
+        let ec_x = generate_random_scalar();
+        register(&auth, &scalar(&auth, 3), &ec_x).await;
+
+        let k = generate_random_scalar();
+        let (r1, r2) = auth.elliptic_curve.compute_public_pair(&k);
+        let response = auth
+            .create_elliptic_curve_authentication_challenge(Request::new(
+                AuthenticationChallengeRequest {
+                    user: USER.to_string(),
+                    r1: r1.compress().to_bytes().to_vec(),
+                    r2: r2.compress().to_bytes().to_vec(),
+                },
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // the elliptic curve attempt cannot be answered with the exponentiation RPC
+        let status = answer(&auth, response.auth_id.clone(), &scalar(&auth, 1))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        // the wrong answer consumed the attempt
+        let c = decode_scalar(&auth.elliptic_curve, "c", &response.c).unwrap();
+        let s = auth
+            .elliptic_curve
+            .compute_solution_for_challenge(&k, &c, &ec_x);
+        let status = auth
+            .verify_elliptic_curve_authentication(Request::new(AuthenticationAnswerRequest {
+                auth_id: response.auth_id,
+                s: s.to_bytes().to_vec(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_exponentiation_group() {
+        let auth = auth_user();
+        let x = auth.protocol.random_scalar();
+        register(&auth, &x, &generate_random_scalar()).await;
+        let registered = auth.store.lookup_user(USER).unwrap().unwrap();
+        assert_eq!(
+            registered.exponentiation_group.as_deref(),
+            Some("rfc5114-2048-224")
+        );
+
+        // a pair registered in another group is not usable in the group of the server
+        let set_group = |group: Option<&str>| {
+            auth.store
+                .modify_user(USER, |entry| {
+                    entry.as_mut().unwrap().exponentiation_group = group.map(str::to_string);
+                })
+                .unwrap()
+        };
+        set_group(Some(NamedGroup::Ffdhe2048.name()));
+        let k = auth.protocol.random_scalar();
+        let (r1, r2) = auth.protocol.compute_public_pair(&k);
+        let status = auth
+            .create_authentication_challenge(Request::new(AuthenticationChallengeRequest {
+                user: USER.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert_eq!(error_info(&status).unwrap().reason, "MISSING_CREDENTIAL");
+
+        // the records older than the group are read in the group of the server
+        set_group(None);
+        let (auth_id, c) = challenge(&auth, &k).await;
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        assert!(answer(&auth, auth_id, &s).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_modes() {
+        let auth = auth_user().with_modes(vec![AuthMode::EllipticCurveChaumPedersen]);
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+
+        let (r1, r2) = auth
+            .protocol
+            .compute_public_pair(&auth.protocol.random_scalar());
+        let status = auth
+            .create_authentication_challenge(Request::new(AuthenticationChallengeRequest {
+                user: USER.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+        assert_eq!(
+            status.message(),
+            "The exponentiation authentication is disabled on this server"
+        );
+        let info = error_info(&status).unwrap();
+        assert_eq!(info.reason, "MODE_DISABLED");
+        assert_eq!(info.metadata["mode"], "exponentiation");
+
+        let t = auth.elliptic_curve.g.compress().to_bytes().to_vec();
+        let status = auth
+            .send_pedersen_commitment(Request::new(PedersenCommitmentRequest {
+                user: USER.to_string(),
+                t,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+
+        // a credential is rotated only with a proof of an enabled mode
+        let new_credential = credential(&auth, &scalar(&auth, 5), &generate_random_scalar());
+        let context = rotation_context(
+            &new_credential,
+            CredentialGroup::Exponentiation,
+            &rotation_nonce(&auth, USER),
+        );
+        let proof = auth.protocol.prove_non_interactive(&x, &context);
+        let status = auth
+            .rotate_credential(Request::new(RotateCredentialRequest {
+                credential: Some(new_credential),
+                group: CredentialGroup::Exponentiation as i32,
+                c: proof.c.to_bytes_be(),
+                s: proof.s.to_bytes_be(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+
+        // and registered only with a credential of an enabled mode
+        let mut request = credential(&auth, &x, &generate_random_scalar());
+        request.user = "bob".to_string();
+        request.ec_y1.clear();
+        request.ec_y2.clear();
+        let status = auth.register(Request::new(request)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "Invalid credential: no credential of an enabled authentication mode"
+        );
+    }
+
+    struct FakeClock(Mutex<SystemTime>);
+
+    impl FakeClock {
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_rejected() {
+        let auth = auth_user();
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+
+        let k = scalar(&auth, 4);
+        let (auth_id, c) = challenge(&auth, &k).await;
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        assert!(answer(&auth, auth_id.clone(), &s).await.is_ok());
+        let status = answer(&auth, auth_id, &s).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // a failed answer also consumes the attempt
+        let (auth_id, c) = challenge(&auth, &k).await;
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        let wrong_s = auth.protocol.scalar_add(&s, &scalar(&auth, 1));
+        let status = answer(&auth, auth_id.clone(), &wrong_s).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let status = answer(&auth, auth_id, &s).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_attempt_expiry() {
+        let clock = Arc::new(FakeClock(Mutex::new(SystemTime::UNIX_EPOCH)));
+        let auth = auth_user()
+            .with_clock(clock.clone())
+            .with_attempt_ttl(Duration::from_secs(10));
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+
+        let k = scalar(&auth, 4);
+        let (auth_id, c) = challenge(&auth, &k).await;
+        clock.advance(Duration::from_secs(11));
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        let status = answer(&auth, auth_id, &s).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // only the attempts older than the TTL are evicted
+        challenge(&auth, &k).await;
+        clock.advance(Duration::from_secs(6));
+        let (auth_id, c) = challenge(&auth, &k).await;
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(auth.sweep_expired_attempts().unwrap(), 1);
+        assert_eq!(auth.sweep_expired_attempts().unwrap(), 0);
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        assert!(answer(&auth, auth_id, &s).await.is_ok());
+    }
+
+    async fn commitment_opening(
+        auth: &AuthUser,
+        ec_x: &Scalar,
+    ) -> Result<Response<CommitmentOpeningResponse>, Status> {
+        let (t, a, b) = auth.elliptic_curve.commit_opening_nonces();
+        let response = auth
+            .send_pedersen_commitment(Request::new(PedersenCommitmentRequest {
+                user: USER.to_string(),
+                t: t.compress().to_bytes().to_vec(),
+            }))
+            .await?
+            .into_inner();
+        let c = decode_scalar(&auth.elliptic_curve, "c", &response.c)?;
+        let (s1, s2) = auth.elliptic_curve.compute_opening_solution(
+            &a,
+            &b,
+            &c,
+            ec_x,
+            &derive_blinding_factor(ec_x, &BLINDING_SALT),
+        );
+        auth.open_commitment(Request::new(CommitmentOpeningAnswerRequest {
+            auth_id: response.auth_id,
+            s1: s1.to_bytes().to_vec(),
+            s2: s2.to_bytes().to_vec(),
+        }))
+        .await
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_rpcs() {
+        let auth = Arc::new(auth_user());
+        let x = scalar(&auth, 3);
+        let ec_x = generate_random_scalar();
+        register(&auth, &x, &ec_x).await;
+
+        // exponentiation and commitment logins interleaved on several threads, with sweeps
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let auth = auth.clone();
+                let x = x.clone();
+                tokio::spawn(async move {
+                    for _ in 0..20 {
+                        if i % 2 == 0 {
+                            let k = auth.protocol.random_scalar();
+                            let (auth_id, c) = challenge(&auth, &k).await;
+                            let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+                            answer(&auth, auth_id, &s).await.unwrap();
+                        } else {
+                            commitment_opening(&auth, &ec_x).await.unwrap();
+                        }
+                        auth.sweep_expired_attempts().unwrap();
+                    }
+                })
+            })
+            .collect();
+        let all_done = async {
+            for task in tasks {
+                task.await.unwrap();
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(60), all_done)
+            .await
+            .expect("the RPCs deadlocked");
+        assert_eq!(auth.store.retain_attempts(&mut |_| false).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_registration_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.db");
+        let ec_x = generate_random_scalar();
+        let auth = auth_user().with_store(Arc::new(SqliteUserStore::open(&path).unwrap()));
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &ec_x).await;
+        let k = scalar(&auth, 4);
+        let (auth_id, c) = challenge(&auth, &k).await;
+        drop(auth);
+
+        // the challenge sent before the restart can still be answered
+        let auth = auth_user().with_store(Arc::new(SqliteUserStore::open(&path).unwrap()));
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        assert!(answer(&auth, auth_id, &s).await.is_ok());
+        assert!(commitment_opening(&auth, &ec_x).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_credential_rotation() {
+        let auth = auth_user();
+        let (x, ec_x) = (scalar(&auth, 3), generate_random_scalar());
+        register(&auth, &x, &ec_x).await;
+
+        // a second registration does not replace the credential
+        let (new_x, new_ec_x) = (scalar(&auth, 5), generate_random_scalar());
+        let new_credential = credential(&auth, &new_x, &new_ec_x);
+        let status = auth
+            .register(Request::new(new_credential.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+
+        let rotate = |credential: RegisterRequest, group: CredentialGroup, c, s| {
+            auth.rotate_credential(Request::new(RotateCredentialRequest {
+                credential: Some(credential),
+                group: group as i32,
+                c,
+                s,
+            }))
+        };
+        let nonce = rotation_nonce(&auth, USER);
+        let context = rotation_context(&new_credential, CredentialGroup::Exponentiation, &nonce);
+        let ec_context = rotation_context(&new_credential, CredentialGroup::EllipticCurve, &nonce);
+        // proofs with the wrong secret, bound to another credential or to another group are
+        // rejected
+        let proof = auth.protocol.prove_non_interactive(&new_x, &context);
+        let status = rotate(
+            new_credential.clone(),
+            CredentialGroup::Exponentiation,
+            proof.c.to_bytes_be(),
+            proof.s.to_bytes_be(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let (c, s) = auth
+            .elliptic_curve
+            .prove_non_interactive(&new_ec_x, &ec_context);
+        let status = rotate(
+            new_credential.clone(),
+            CredentialGroup::EllipticCurve,
+            c.to_bytes().to_vec(),
+            s.to_bytes().to_vec(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let (c, s) = auth
+            .elliptic_curve
+            .prove_non_interactive(&ec_x, &ec_context);
+        let status = rotate(
+            credential(&auth, &scalar(&auth, 7), &new_ec_x),
+            CredentialGroup::EllipticCurve,
+            c.to_bytes().to_vec(),
+            s.to_bytes().to_vec(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let (c, s) = auth.elliptic_curve.prove_non_interactive(&ec_x, &context);
+        let status = rotate(
+            new_credential.clone(),
+            CredentialGroup::EllipticCurve,
+            c.to_bytes().to_vec(),
+            s.to_bytes().to_vec(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert!(commitment_opening(&auth, &ec_x).await.is_ok());
+
+        // the sessions opened with the previous secret are revoked by the rotation
+        let session_id = try_login(&auth, &x).await.unwrap();
+        let proof = auth.protocol.prove_non_interactive(&x, &context);
+        let replayed = (proof.c.to_bytes_be(), proof.s.to_bytes_be());
+        rotate(
+            new_credential.clone(),
+            CredentialGroup::Exponentiation,
+            proof.c.to_bytes_be(),
+            proof.s.to_bytes_be(),
+        )
+        .await
+        .unwrap();
+        assert!(commitment_opening(&auth, &ec_x).await.is_err());
+        assert!(validate(&auth, &session_id).await.is_err());
+        assert!(try_login(&auth, &new_x).await.is_ok());
+        assert!(commitment_opening(&auth, &new_ec_x).await.is_ok());
+
+        // a captured rotation cannot be replayed once the credential is rotated back
+        let old_credential = credential(&auth, &x, &ec_x);
+        let (c, s) = auth.elliptic_curve.prove_non_interactive(
+            &new_ec_x,
+            &rotation_context(
+                &old_credential,
+                CredentialGroup::EllipticCurve,
+                &rotation_nonce(&auth, USER),
+            ),
+        );
+        rotate(
+            old_credential,
+            CredentialGroup::EllipticCurve,
+            c.to_bytes().to_vec(),
+            s.to_bytes().to_vec(),
+        )
+        .await
+        .unwrap();
+        assert!(try_login(&auth, &x).await.is_ok());
+        let status = rotate(
+            new_credential.clone(),
+            CredentialGroup::Exponentiation,
+            replayed.0,
+            replayed.1,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+
+        // a challenge sent before a rotation is not answered after it, even with the public
+        // pair kept by the new credential
+        let k = auth.protocol.random_scalar();
+        let (auth_id, c) = challenge(&auth, &k).await;
+        let kept = credential(&auth, &x, &new_ec_x);
+        let (ec_c, ec_s) = auth.elliptic_curve.prove_non_interactive(
+            &ec_x,
+            &rotation_context(
+                &kept,
+                CredentialGroup::EllipticCurve,
+                &rotation_nonce(&auth, USER),
+            ),
+        );
+        rotate(
+            kept,
+            CredentialGroup::EllipticCurve,
+            ec_c.to_bytes().to_vec(),
+            ec_s.to_bytes().to_vec(),
+        )
+        .await
+        .unwrap();
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        let status = answer(&auth, auth_id, &s).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // the session of a login racing with a rotation is rejected and removed
+        let session_id = login(&auth, &x).await;
+        auth.store
+            .modify_user(USER, |entry| {
+                entry.as_mut().unwrap().rotated_at = Some(SystemTime::now());
+            })
+            .unwrap();
+        assert!(validate(&auth, &session_id).await.is_err());
+        assert!(auth
+            .store
+            .modify_session(&session_id, |entry| entry.is_none())
+            .unwrap());
+
+        let mut unknown = new_credential;
+        unknown.user = "bob".to_string();
+        let context = rotation_context(&unknown, CredentialGroup::Exponentiation, &[]);
+        let proof = auth.protocol.prove_non_interactive(&new_x, &context);
+        let status = rotate(
+            unknown,
+            CredentialGroup::Exponentiation,
+            proof.c.to_bytes_be(),
+            proof.s.to_bytes_be(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_arguments() {
+        let auth = auth_user();
+        let ec_x = generate_random_scalar();
+        let valid = credential(&auth, &scalar(&auth, 3), &ec_x);
+        let invalid_register = |update: fn(&mut RegisterRequest)| {
+            let mut request = valid.clone();
+            update(&mut request);
+            auth.register(Request::new(request))
+        };
+        // 1 is the identity, 257 bytes are above p and 5 is outside of the subgroup of order q
+        for (update, message) in [
+            (
+                (|r| r.user.clear()) as fn(&mut RegisterRequest),
+                "Invalid user: empty",
+            ),
+            (
+                |r| r.y1 = vec![1],
+                "Invalid y1: the element is the identity",
+            ),
+            (|r| r.y1 = vec![0], "Invalid y1: the value is out of range"),
+            (
+                |r| r.y2 = vec![0xff; 257],
+                "Invalid y2: the value is out of range",
+            ),
+            (
+                |r| r.y2 = vec![5],
+                "Invalid y2: the element is not in the prime order subgroup",
+            ),
+            (
+                |r| r.y1 = vec![0, 4],
+                "Invalid y1: the encoding is not canonical",
+            ),
+            (|r| r.y2.clear(), "Invalid y2: the value is empty"),
+            (
+                |r| r.ec_y1 = vec![0xff; 32],
+                "Invalid ec_y1: the encoding is not canonical",
+            ),
+            (
+                |r| r.ec_y2 = vec![0; 32],
+                "Invalid ec_y2: the element is the identity",
+            ),
+            (
+                |r| r.pedersen_commitment = vec![1; 3],
+                "Invalid pedersen_commitment: expected 32 bytes, found 3",
+            ),
+            (
+                |r| r.blinding_salt.clear(),
+                "Invalid blinding_salt: 0 bytes, at least 8 are required",
+            ),
+            (
+                |r| r.salt = vec![1; 4],
+                "Invalid salt: 4 bytes, at least 8 are required",
+            ),
+            (
+                |r| r.kdf_parameters = None,
+                "Invalid kdf_parameters: required with the salt",
+            ),
+        ] {
+            let status = invalid_register(update).await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+            assert_eq!(status.message(), message);
+        }
+        register(&auth, &scalar(&auth, 3), &ec_x).await;
+
+        let status = auth
+            .create_authentication_challenge(Request::new(AuthenticationChallengeRequest {
+                user: USER.to_string(),
+                r1: vec![1],
+                r2: vec![4],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "Invalid r1: the element is the identity");
+        let info = error_info(&status).unwrap();
+        assert_eq!(info.reason, "IDENTITY_ELEMENT");
+        assert_eq!(info.metadata["field"], "r1");
+
+        // malformed answers are rejected without a panic
+        let (auth_id, _) = challenge(&auth, &scalar(&auth, 2)).await;
+        let status = auth
+            .verify_authentication(Request::new(AuthenticationAnswerRequest {
+                auth_id,
+                s: vec![0xff; 29],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "Invalid s: the value is out of range");
+        let response = auth
+            .send_pedersen_commitment(Request::new(PedersenCommitmentRequest {
+                user: USER.to_string(),
+                t: auth
+                    .elliptic_curve
+                    .compute_public_pair(&ec_x)
+                    .0
+                    .compress()
+                    .to_bytes()
+                    .to_vec(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let status = auth
+            .open_commitment(Request::new(CommitmentOpeningAnswerRequest {
+                auth_id: response.auth_id,
+                s1: vec![0xff; 32],
+                s2: vec![],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "Invalid s1: the encoding is not canonical"
+        );
+    }
+
+    async fn try_login(auth: &AuthUser, x: &ModpScalar) -> Result<String, Status> {
+        let k = auth.protocol.random_scalar();
+        let (auth_id, c) = challenge(auth, &k).await;
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, x);
+        answer(auth, auth_id, &s)
+            .await
+            .map(|response| response.into_inner().session_id)
+    }
+
+    async fn login(auth: &AuthUser, x: &ModpScalar) -> String {
+        try_login(auth, x).await.unwrap()
+    }
+
+    async fn validate(
+        auth: &AuthUser,
+        session_id: &str,
+    ) -> Result<ValidateSessionResponse, Status> {
+        auth.validate_session(Request::new(ValidateSessionRequest {
+            session_id: session_id.to_string(),
+            client_fingerprint: String::new(),
+        }))
+        .await
+        .map(Response::into_inner)
+    }
+
+    #[tokio::test]
+    async fn test_sessions() {
+        let clock = Arc::new(FakeClock(Mutex::new(SystemTime::UNIX_EPOCH)));
+        let auth = auth_user()
+            .with_clock(clock.clone())
+            .with_session_timeouts(SessionTimeouts {
+                idle: Duration::from_secs(10),
+                absolute: Duration::from_secs(25),
+            });
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+
+        // two devices logged in at once
+        let session1 = login(&auth, &x).await;
+        let session2 = login(&auth, &x).await;
+        let validated = validate(&auth, &session1).await.unwrap();
+        assert_eq!(validated.user, USER);
+        assert_eq!(validated.expires_at, 10);
+        assert!(validate(&auth, &session2).await.is_ok());
+
+        // the validation restarts the idle timeout, up to the absolute timeout
+        clock.advance(Duration::from_secs(8));
+        assert_eq!(validate(&auth, &session1).await.unwrap().expires_at, 18);
+        clock.advance(Duration::from_secs(8));
+        let status = validate(&auth, &session2).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        let refreshed = auth
+            .refresh_session(Request::new(RefreshSessionRequest {
+                session_id: session1.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_ne!(refreshed.session_id, session1);
+        assert_eq!(refreshed.expires_at, 25);
+        assert!(validate(&auth, &session1).await.is_err());
+        clock.advance(Duration::from_secs(8));
+        assert!(validate(&auth, &refreshed.session_id).await.is_ok());
+        clock.advance(Duration::from_secs(2));
+        assert!(validate(&auth, &refreshed.session_id).await.is_err());
+        assert_eq!(auth.sweep_expired_sessions().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_logout() {
+        let auth = auth_user();
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+        let sessions = [
+            login(&auth, &x).await,
+            login(&auth, &x).await,
+            login(&auth, &x).await,
+        ];
+
+        let logout = |session_id: &str, all_sessions| {
+            auth.logout(Request::new(LogoutRequest {
+                session_id: session_id.to_string(),
+                all_sessions,
+            }))
+        };
+        logout(&sessions[0], false).await.unwrap();
+        assert!(validate(&auth, &sessions[0]).await.is_err());
+        assert!(validate(&auth, &sessions[1]).await.is_ok());
+        let status = logout(&sessions[0], false).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        logout(&sessions[1], true).await.unwrap();
+        assert!(validate(&auth, &sessions[1]).await.is_err());
+        assert!(validate(&auth, &sessions[2]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signed_session_tokens() {
+        let clock = Arc::new(FakeClock(Mutex::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1000),
+        )));
+        let issuer = TokenIssuer::new(SigningKey::generate("k1").unwrap());
+        let verifier = TokenVerifier::new([issuer.verifying_key()]);
+        let auth = auth_user()
+            .with_clock(clock.clone())
+            .with_session_timeouts(SessionTimeouts {
+                idle: Duration::from_secs(10),
+                absolute: Duration::from_secs(25),
+            })
+            .with_session_tokens(SessionTokens::Signed {
+                issuer,
+                scopes: vec!["read".to_string()],
+            });
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+
+        // the token is verified without the store of the server
+        let token = login(&auth, &x).await;
+        let claims = verifier.verify(&token, clock.now()).unwrap();
+        assert_eq!(claims.sub, USER);
+        assert_eq!(claims.method, "exponentiation");
+        assert_eq!((claims.iat, claims.exp), (1000, 1025));
+        assert_eq!(claims.scopes, ["read"]);
+        assert_eq!(claims.cnf, None);
+        assert_eq!(validate(&auth, &token).await.unwrap().user, USER);
+
+        clock.advance(Duration::from_secs(5));
+        let refreshed = auth
+            .refresh_session(Request::new(RefreshSessionRequest {
+                session_id: token.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let refreshed_claims = verifier.verify(&refreshed.session_id, clock.now()).unwrap();
+        assert_eq!(refreshed_claims.exp, claims.exp);
+        assert_ne!(refreshed_claims.jti, claims.jti);
+        assert!(validate(&auth, &token).await.is_err());
+
+        // the method of a session stored without its mode is not guessed
+        auth.store
+            .modify_session(&refreshed.session_id, |entry| {
+                entry.as_mut().unwrap().mode = None;
+            })
+            .unwrap();
+        let refreshed = auth
+            .refresh_session(Request::new(RefreshSessionRequest {
+                session_id: refreshed.session_id,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let refreshed_claims = verifier.verify(&refreshed.session_id, clock.now()).unwrap();
+        assert_eq!(refreshed_claims.method, "unknown");
+    }
+
+    #[tokio::test]
+    async fn test_remote_session_validation() {
+        let auth = Arc::new(auth_user());
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+        let session_id = login(&auth, &x).await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            Server::builder()
+                .add_service(AuthServer::from_arc(auth.clone()))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let client = AuthClient::connect(url).await.unwrap();
+
+        // the ErrorInfo of the statuses goes through the transport
+        let status = client
+            .clone()
+            .register(Request::new(credential(
+                &auth,
+                &x,
+                &generate_random_scalar(),
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+        let info = error_info(&status).unwrap();
+        assert_eq!(info.reason, "USER_EXISTS");
+        assert_eq!(info.metadata["user"], USER);
+
+        // a downstream service answering with the user of the session
+        let mut service = SessionAuthLayer::remote(client).layer(tower::service_fn(
+            |request: http::Request<()>| async move {
+                let user = request.extensions().get::<AuthenticatedUser>().unwrap();
+                let response = http::Response::builder()
+                    .header("user", user.user.as_str())
+                    .body(tonic::body::empty_body())
+                    .unwrap();
+                Ok::<_, std::convert::Infallible>(response)
+            },
+        ));
+        let request = |session_id: &str| {
+            http::Request::builder()
+                .header(AUTHORIZATION_HEADER, format!("Bearer {}", session_id))
+                .body(())
+                .unwrap()
+        };
+        let response = service.call(request(&session_id)).await.unwrap();
+        assert_eq!(response.headers()["user"], USER);
+
+        // a session bound to a client certificate is rejected without it
+        let bound_session_id = login(&auth, &x).await;
+        auth.store
+            .modify_session(&bound_session_id, |entry| {
+                entry.as_mut().unwrap().client_fingerprint = Some("fingerprint".to_string());
+            })
+            .unwrap();
+        auth.logout(Request::new(LogoutRequest {
+            session_id: session_id.clone(),
+            all_sessions: false,
+        }))
+        .await
+        .unwrap();
+        for session_id in [session_id, bound_session_id] {
+            let response = service.call(request(&session_id)).await.unwrap();
+            assert!(response.headers().get("user").is_none());
+            assert_eq!(
+                response.headers()["grpc-status"],
+                (Code::Unauthenticated as i32).to_string()
+            );
+        }
+    }
+
+    ///
+    /// PEM files of a test CA and of the certificates it issues, server or client
+    ///
+    struct TestPki {
+        dir: tempfile::TempDir,
+        ca: rcgen::Certificate,
+    }
+
+    impl TestPki {
+        fn new() -> Self {
+            let mut params = rcgen::CertificateParams::default();
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, "zkp test CA");
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            let ca = rcgen::Certificate::from_params(params).unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("ca.pem"), ca.serialize_pem().unwrap()).unwrap();
+            TestPki { dir, ca }
+        }
+
+        fn path(&self, name: &str) -> std::path::PathBuf {
+            self.dir.path().join(name)
+        }
+
+        ///
+        /// Writes `<name>.pem` and `<name>.key`, returns their paths
+        ///
+        fn issue(
+            &self,
+            name: &str,
+            usage: rcgen::ExtendedKeyUsagePurpose,
+        ) -> (std::path::PathBuf, std::path::PathBuf) {
+            let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string()]);
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, name);
+            params.extended_key_usages = vec![usage];
+            let cert = rcgen::Certificate::from_params(params).unwrap();
+            let (cert_path, key_path) = (
+                self.path(&format!("{}.pem", name)),
+                self.path(&format!("{}.key", name)),
+            );
+            std::fs::write(
+                &cert_path,
+                cert.serialize_pem_with_signer(&self.ca).unwrap(),
+            )
+            .unwrap();
+            std::fs::write(&key_path, cert.serialize_private_key_pem()).unwrap();
+            (cert_path, key_path)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls_sessions() {
+        let issuer = TokenIssuer::new(SigningKey::generate("k1").unwrap());
+        let verifier = TokenVerifier::new([issuer.verifying_key()]);
+        let auth = Arc::new(auth_user().with_session_tokens(SessionTokens::Signed {
+            issuer,
+            scopes: Vec::new(),
+        }));
+        let x = scalar(&auth, 3);
+        register(&auth, &x, &generate_random_scalar()).await;
+
+        let pki = TestPki::new();
+        let (cert, key) = pki.issue("server", rcgen::ExtendedKeyUsagePurpose::ServerAuth);
+        let tls = server_tls_config(&TlsConfig {
+            cert: Some(cert),
+            key: Some(key),
+            client_ca: Some(pki.path("ca.pem")),
+        })
+        .unwrap()
+        .unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            Server::builder()
+                .tls_config(tls)
+                .unwrap()
+                .add_service(AuthServer::from_arc(auth.clone()))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let connect = |identity: Option<&str>| {
+            let mut tls = ClientTls::new(pki.path("ca.pem")).with_domain("localhost");
+            if let Some(name) = identity {
+                let (cert, key) = pki.issue(name, rcgen::ExtendedKeyUsagePurpose::ClientAuth);
+                tls = tls.with_identity(cert, key);
+            }
+            let endpoint = tonic::transport::Channel::from_shared(url.clone())
+                .unwrap()
+                .tls_config(tls.client_tls_config().unwrap())
+                .unwrap();
+            async move { endpoint.connect().await.map(AuthClient::new) }
+        };
+
+        // the session of a login over mutual TLS is bound to the client certificate
+        let mut alice = connect(Some("alice-laptop")).await.unwrap();
+        let k = auth.protocol.random_scalar();
+        let (r1, r2) = auth.protocol.compute_public_pair(&k);
+        let challenge = alice
+            .create_authentication_challenge(Request::new(AuthenticationChallengeRequest {
+                user: USER.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let c = auth.protocol.decode_scalar(&challenge.c).unwrap();
+        let s = auth.protocol.compute_solution_for_challenge(&k, &c, &x);
+        let session_id = alice
+            .verify_authentication(Request::new(AuthenticationAnswerRequest {
+                auth_id: challenge.auth_id,
+                s: auth.protocol.encode_scalar(&s),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .session_id;
+
+        // the session is only valid for the client of the login, not even for another
+        // certificate of the same subject, unless a service validates it for that client
+        let validate = |session_id: &str, client_fingerprint: &str| {
+            Request::new(ValidateSessionRequest {
+                session_id: session_id.to_string(),
+                client_fingerprint: client_fingerprint.to_string(),
+            })
+        };
+        let validated = alice
+            .validate_session(validate(&session_id, ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(validated.user, USER);
+        assert_eq!(validated.client_subject, "CN=alice-laptop");
+        assert_eq!(validated.client_fingerprint.len(), 43);
+        let claims = verifier.verify(&session_id, SystemTime::now()).unwrap();
+        assert_eq!(
+            claims.cnf.map(|cnf| cnf.x5t_s256).as_ref(),
+            Some(&validated.client_fingerprint)
+        );
+        let mut other = connect(Some("alice-laptop")).await.unwrap();
+        let status = other
+            .validate_session(validate(&session_id, ""))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        let validated_for_alice = other
+            .validate_session(validate(&session_id, &validated.client_fingerprint))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(validated_for_alice, validated);
+        let refresh = |session_id: &str| {
+            Request::new(RefreshSessionRequest {
+                session_id: session_id.to_string(),
+            })
+        };
+        let status = other
+            .refresh_session(refresh(&session_id))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        let status = other
+            .logout(Request::new(LogoutRequest {
+                session_id: session_id.clone(),
+                all_sessions: true,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        let refreshed = alice.refresh_session(refresh(&session_id)).await.unwrap();
+        assert!(!refreshed.into_inner().session_id.is_empty());
+
+        // a client without certificate is rejected by the handshake
+        let rejected = match connect(None).await {
+            Ok(mut anonymous) => anonymous
+                .get_salt(Request::new(SaltRequest {
+                    user: USER.to_string(),
+                }))
+                .await
+                .is_err(),
+            Err(_) => true,
+        };
+        assert!(rejected);
+    }
+}
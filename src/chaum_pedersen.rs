@@ -2,39 +2,204 @@
 // sub group cyclic (23 - 1 / 2) => Q = Z/11
 // choose g and h in Q
 
+use crate::montgomery::{with_backend, ModpBackend, ModpScalar, ScalarLimbs, MAX_P_BITS};
+use crate::named_groups::hex_bytes;
+use crate::primality::{is_probable_prime, miller_rabin, MILLER_RABIN_ROUNDS};
+use crate::sigma;
 use num_bigint::{BigUint, RandBigInt};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
 
+/// Domain separation tag for the Fiat-Shamir challenge
+const FIAT_SHAMIR_DOMAIN: &[u8] = b"zkp-protocol-ex/chaum-pedersen/fiat-shamir/v1";
+/// Tag of the generator derivation (FIPS 186-4 A.2.3 "ggen")
+const GENERATOR_TAG: &[u8] = b"ggen";
+/// Index of the second generator h in `derive_generator`
+pub const H_GENERATOR_INDEX: u8 = 1;
+/// Published label of h for the RFC 5114 1024-bit group
+pub const RFC5114_1024_160_H_LABEL: &str = "zkp-protocol-ex/h/rfc5114-1024-160";
+
+#[derive(Clone)]
 pub struct ZKPProtocol {
     /// a large prime order exp: Z/pZ
-    pub p: BigUint,
+    p: BigUint,
     /// A prime order sub group Z/qZ  (q = (p- 1)/2)
-    pub q: BigUint,
+    q: BigUint,
     /// A generator of Z/qZ
-    pub g: BigUint,
+    g: BigUint,
     /// A generator of Z/qZ
-    pub h: BigUint,
+    h: BigUint,
+    /// fixed-width arithmetic of the parameters, built once by `new`
+    backend: Arc<ModpBackend>,
+}
+
+impl PartialEq for ZKPProtocol {
+    fn eq(&self, other: &Self) -> bool {
+        self.p == other.p && self.q == other.q && self.g == other.g && self.h == other.h
+    }
+}
+
+impl Eq for ZKPProtocol {}
+
+impl fmt::Debug for ZKPProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZKPProtocol")
+            .field("p", &self.p)
+            .field("q", &self.q)
+            .field("g", &self.g)
+            .field("h", &self.h)
+            .finish_non_exhaustive()
+    }
 }
 
 impl ZKPProtocol {
+    ///
+    /// Build the parameters and their Montgomery backend. Only the sizes and the parity of
+    /// p and q are checked here, `validate` checks the structure of the group
+    ///
+    pub fn new(p: BigUint, q: BigUint, g: BigUint, h: BigUint) -> Result<Self, ParameterError> {
+        let backend = Arc::new(ModpBackend::new(&p, &q, &g, &h)?);
+        Ok(ZKPProtocol {
+            p,
+            q,
+            g,
+            h,
+            backend,
+        })
+    }
+
+    ///
+    /// Build the parameters with a second generator h derived from a published label,
+    /// so that nobody knows log_g(h) (anyone knowing it can forge proofs)
+    ///
+    pub fn with_derived_h(
+        p: BigUint,
+        q: BigUint,
+        g: BigUint,
+        label: &[u8],
+    ) -> Result<ZKPProtocol, ParameterError> {
+        let h = derive_generator(&p, &q, label, H_GENERATOR_INDEX)?;
+        ZKPProtocol::new(p, q, g, h)
+    }
+
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    pub fn g(&self) -> &BigUint {
+        &self.g
+    }
+
+    pub fn h(&self) -> &BigUint {
+        &self.h
+    }
+
+    pub(crate) fn backend(&self) -> &ModpBackend {
+        &self.backend
+    }
+
+    ///
+    /// Scalar of Z/qZ of a public value, reduced mod q
+    ///
+    pub fn scalar_from_biguint(&self, value: &BigUint) -> ModpScalar {
+        with_backend!(self.backend(), group => group.scalar_from_biguint(value).wrap())
+    }
+
+    ///
+    /// Audit: re-derive h from the published label and check it matches the parameters
+    ///
+    pub fn verify_h_derivation(&self, label: &[u8]) -> bool {
+        derive_generator(&self.p, &self.q, label, H_GENERATOR_INDEX).is_ok_and(|h| h == self.h)
+    }
+
+    ///
+    /// Check that the parameters define a usable Chaum-Pedersen group:
+    /// p and q are probable primes, q divides p - 1, g and h are different from 1
+    /// and generate the subgroup of order q, and p and q are large enough for the security level
+    ///
+    pub fn validate(&self, level: &SecurityLevel) -> Result<(), ParameterError> {
+        let one = BigUint::from(1u32);
+        let p_bits = self.p.bits();
+        let q_bits = self.q.bits();
+        if p_bits < level.min_p_bits {
+            return Err(ParameterError::PTooSmall {
+                bits: p_bits,
+                min_bits: level.min_p_bits,
+            });
+        }
+        if p_bits > MAX_P_BITS {
+            return Err(ParameterError::PTooLarge {
+                bits: p_bits,
+                max_bits: MAX_P_BITS,
+            });
+        }
+        if q_bits < level.min_q_bits {
+            return Err(ParameterError::QTooSmall {
+                bits: q_bits,
+                min_bits: level.min_q_bits,
+            });
+        }
+        if !is_probable_prime(&self.p, MILLER_RABIN_ROUNDS) {
+            return Err(ParameterError::PNotPrime);
+        }
+        if !is_probable_prime(&self.q, MILLER_RABIN_ROUNDS) {
+            return Err(ParameterError::QNotPrime);
+        }
+        if (&self.p - &one) % &self.q != BigUint::from(0u32) {
+            return Err(ParameterError::QDoesNotDivideOrder);
+        }
+        for (name, generator) in [("g", &self.g), ("h", &self.h)] {
+            if *generator <= one || *generator >= self.p {
+                return Err(ParameterError::InvalidGenerator {
+                    name,
+                    reason: "must be in ]1, p[",
+                });
+            }
+            if generator.modpow(&self.q, &self.p) != one {
+                return Err(ParameterError::InvalidGenerator {
+                    name,
+                    reason: "does not have order q",
+                });
+            }
+        }
+        if self.g == self.h {
+            return Err(ParameterError::InvalidGenerator {
+                name: "h",
+                reason: "must be different from g",
+            });
+        }
+        Ok(())
+    }
+
     ///
     /// compute the public information from g, h and the secret password x (done on the prover side)
     /// y1 = g^x mode p and y2 = h^x mod p
     /// r1 = g^k mod p and r2 = h^k mod p
     ///
-    pub fn compute_public_pair(&self, x: &BigUint) -> (BigUint, BigUint) {
-        (self.g.modpow(x, &self.p), self.h.modpow(x, &self.p))
+    pub fn compute_public_pair(&self, x: &ModpScalar) -> (BigUint, BigUint) {
+        sigma::chaum_pedersen::public_pair(self, x)
     }
 
     ///
     /// solve the challenge c send by the server
     /// k random in Z/qZ generated by the prover
     /// c random in Z/qZ generated byt the server
+    /// s = k - c * x mod q
     ///
-    pub fn compute_solution_for_challenge(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
-        if *k > c * x {
-            return k - c * x.modpow(&BigUint::from(1u32), &self.q);
-        }
-        &self.q - (c * x - k).modpow(&BigUint::from(1u32), &self.q)
+    pub fn compute_solution_for_challenge(
+        &self,
+        k: &ModpScalar,
+        c: &ModpScalar,
+        x: &ModpScalar,
+    ) -> ModpScalar {
+        sigma::chaum_pedersen::solve(self, k, c, x)
     }
 
     ///
@@ -44,21 +209,196 @@ impl ZKPProtocol {
     ///
     pub fn verify_solution(
         &self,
-        c: &BigUint,
-        s: &BigUint,
+        c: &ModpScalar,
+        s: &ModpScalar,
         r1: &BigUint,
         r2: &BigUint,
         y1: &BigUint,
         y2: &BigUint,
     ) -> bool {
-        (*r1 == ((self.g.modpow(s, &self.p)) * y1.modpow(c, &self.p))
-            .modpow(&BigUint::from(1u32), &self.p))
-            && (*r2
-                == ((self.h.modpow(s, &self.p)) * y2.modpow(c, &self.p))
-                    .modpow(&BigUint::from(1u32), &self.p))
+        sigma::chaum_pedersen::verify(self, c, s, r1, r2, y1, y2)
+    }
+
+    ///
+    /// Non-interactive proof (prover side): the challenge c is not sent by the server
+    /// but derived from the public data with the Fiat-Shamir heuristic
+    /// c = SHA-256(domain || p || q || g || h || y1 || y2 || r1 || r2 || context) mod q
+    /// every input is length-prefixed so that distinct inputs never hash the same bytes
+    ///
+    pub fn prove_non_interactive(&self, x: &ModpScalar, context: &[u8]) -> Proof {
+        let (c, s) =
+            sigma::chaum_pedersen::prove_non_interactive(self, FIAT_SHAMIR_DOMAIN, x, context);
+        Proof {
+            c: c.to_biguint(),
+            s: s.to_biguint(),
+        }
+    }
+
+    ///
+    /// Verify a non-interactive proof (verifier side)
+    /// r1 = g^s * y1^c mod p and r2 = h^s * y2^c mod p are recomputed from the proof,
+    /// the proof is valid if hashing them gives back the challenge c
+    ///
+    pub fn verify_non_interactive(
+        &self,
+        proof: &Proof,
+        y1: &BigUint,
+        y2: &BigUint,
+        context: &[u8],
+    ) -> bool {
+        if proof.c >= self.q || proof.s >= self.q {
+            return false;
+        }
+        sigma::chaum_pedersen::verify_non_interactive(
+            self,
+            FIAT_SHAMIR_DOMAIN,
+            &self.scalar_from_biguint(&proof.c),
+            &self.scalar_from_biguint(&proof.s),
+            y1,
+            y2,
+            context,
+        )
+    }
+}
+
+///
+/// Minimum sizes in bits of p and q required by `ZKPProtocol::validate`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityLevel {
+    pub min_p_bits: u64,
+    pub min_q_bits: u64,
+}
+
+impl SecurityLevel {
+    /// Only the structure of the group is checked (toy groups for tests)
+    pub const NONE: SecurityLevel = SecurityLevel {
+        min_p_bits: 0,
+        min_q_bits: 0,
+    };
+    /// 80-bit security (legacy, 1024-bit p and 160-bit q)
+    pub const BITS_80: SecurityLevel = SecurityLevel {
+        min_p_bits: 1024,
+        min_q_bits: 160,
+    };
+    /// 112-bit security (2048-bit p and 224-bit q)
+    pub const BITS_112: SecurityLevel = SecurityLevel {
+        min_p_bits: 2048,
+        min_q_bits: 224,
+    };
+    /// 128-bit security (3072-bit p and 256-bit q)
+    pub const BITS_128: SecurityLevel = SecurityLevel {
+        min_p_bits: 3072,
+        min_q_bits: 256,
+    };
+
+    ///
+    /// Level of 80, 112 or 128 bits, None for the other values
+    ///
+    pub fn from_bits(bits: u32) -> Option<SecurityLevel> {
+        match bits {
+            80 => Some(SecurityLevel::BITS_80),
+            112 => Some(SecurityLevel::BITS_112),
+            128 => Some(SecurityLevel::BITS_128),
+            _ => None,
+        }
+    }
+}
+
+///
+/// Reason why `ZKPProtocol::new` or `ZKPProtocol::validate` rejected the parameters
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParameterError {
+    PTooSmall {
+        bits: u64,
+        min_bits: u64,
+    },
+    PTooLarge {
+        bits: u64,
+        max_bits: u64,
+    },
+    QTooSmall {
+        bits: u64,
+        min_bits: u64,
+    },
+    /// q is wider than the scalars of the backend selected for p
+    QTooLarge {
+        bits: u64,
+        max_bits: u64,
+    },
+    PNotPrime,
+    QNotPrime,
+    QDoesNotDivideOrder,
+    InvalidGenerator {
+        name: &'static str,
+        reason: &'static str,
+    },
+    InvalidSizes {
+        bits_p: u64,
+        bits_q: u64,
+    },
+    CertificateMismatch(&'static str),
+    /// `derive_generator` found no element of order q, q does not divide p - 1
+    GeneratorNotFound,
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::PTooSmall { bits, min_bits } => {
+                write!(f, "p has {} bits, at least {} are required", bits, min_bits)
+            }
+            ParameterError::PTooLarge { bits, max_bits } => {
+                write!(f, "p has {} bits, at most {} are supported", bits, max_bits)
+            }
+            ParameterError::QTooSmall { bits, min_bits } => {
+                write!(f, "q has {} bits, at least {} are required", bits, min_bits)
+            }
+            ParameterError::QTooLarge { bits, max_bits } => {
+                write!(f, "q has {} bits, at most {} are supported", bits, max_bits)
+            }
+            ParameterError::PNotPrime => write!(f, "p is not prime"),
+            ParameterError::QNotPrime => write!(f, "q is not prime"),
+            ParameterError::QDoesNotDivideOrder => write!(f, "q does not divide p - 1"),
+            ParameterError::InvalidGenerator { name, reason } => {
+                write!(f, "generator {} {}", name, reason)
+            }
+            ParameterError::InvalidSizes { bits_p, bits_q } => write!(
+                f,
+                "cannot generate a group with a {}-bit p and a {}-bit q",
+                bits_p, bits_q
+            ),
+            ParameterError::CertificateMismatch(field) => {
+                write!(
+                    f,
+                    "the certificate does not match the parameters: {}",
+                    field
+                )
+            }
+            ParameterError::GeneratorNotFound => {
+                write!(f, "no generator of order q can be derived from the seed")
+            }
+        }
     }
 }
 
+impl std::error::Error for ParameterError {}
+
+///
+/// Non-interactive Chaum-Pedersen proof: challenge c and solution s
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof {
+    pub c: BigUint,
+    pub s: BigUint,
+}
+
+pub(crate) fn absorb(hasher: &mut Sha256, data: &[u8]) {
+    hasher.update((data.len() as u64).to_be_bytes());
+    hasher.update(data);
+}
+
 ///
 /// generate random value in Z/qZ
 ///
@@ -67,70 +407,374 @@ pub fn generate_random_value(max: &BigUint) -> BigUint {
     rng.gen_biguint_below(max)
 }
 
+///
+/// Toy group of order 11 in Z/23Z*, its constants fit the backend
+///
 pub fn get_fixed_zkp_params() -> ZKPProtocol {
-    return ZKPProtocol {
-        p: BigUint::from(23u32),
-        q: BigUint::from(11u32),
-        g: BigUint::from(4u32),
-        h: BigUint::from(9u32),
-    };
+    ZKPProtocol::new(
+        BigUint::from(23u32),
+        BigUint::from(11u32),
+        BigUint::from(4u32),
+        BigUint::from(9u32),
+    )
+    .expect("23 and 11 are odd and 4 and 9 are below 23")
 }
 
-// https://datatracker.ietf.org/doc/html/rfc5114 (not tested yet)
+// RFC 5114 section 2.1, 1024-bit MODP group with 160-bit prime order subgroup
+const RFC5114_1024_160_P: [u8; 128] = hex_bytes(concat!(
+    "B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B61",
+    "6073E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BF",
+    "ACCBDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0",
+    "A151AF5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371",
+));
+const RFC5114_1024_160_G: [u8; 128] = hex_bytes(concat!(
+    "A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31",
+    "266FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4",
+    "D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28A",
+    "D662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5",
+));
+const RFC5114_1024_160_Q: [u8; 20] = hex_bytes("F518AA8781A8DF278ABA4E7D64B7CB9D49462353");
+
+///
+/// RFC 5114 1024-bit group, the constants are valid and h is derived from them
+/// (`test_h_derivation`), the construction does not fail
+/// https://datatracker.ietf.org/doc/html/rfc5114
+///
 pub fn generate_1024bit_group_with_160bit_constants() -> ZKPProtocol {
     // The prime
-    let p = BigUint::from_bytes_be(&hex::decode("B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B616073E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BFACCBDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0A151AF5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371").unwrap());
+    let p = BigUint::from_bytes_be(&RFC5114_1024_160_P);
 
     // The generator
-    let g = BigUint::from_bytes_be(&hex::decode("A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31266FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5").unwrap());
+    let g = BigUint::from_bytes_be(&RFC5114_1024_160_G);
 
     // The generator generates a prime-order subgroup of size:
-    let q =
-        BigUint::from_bytes_be(&hex::decode("F518AA8781A8DF278ABA4E7D64B7CB9D49462353").unwrap());
+    let q = BigUint::from_bytes_be(&RFC5114_1024_160_Q);
 
-    // Another generator:
-    // h = g^i, where i == 266FEA1E5C41564B777E69
-    let i = BigUint::from_bytes_be(&hex::decode("266FEA1E5C41564B777E69").unwrap());
-    let h = g.modpow(&i, &p);
+    // Another generator, derived from a published label
+    ZKPProtocol::with_derived_h(p, q, g, RFC5114_1024_160_H_LABEL.as_bytes())
+        .expect("the RFC 5114 1024-bit group has a subgroup of order q")
+}
 
-    ZKPProtocol { p, q, g, h }
+///
+/// Derive a generator of the subgroup of order q from a public seed, in the spirit of
+/// FIPS 186-4 A.2.3: for count = 1, 2, ... the seed is expanded with SHA-256 into
+/// W = SHA-256(seed || "ggen" || index || count || 0) || SHA-256(... || 1) || ...
+/// (64 bits longer than p), and the generator is W^((p - 1) / q) mod p if it is not 1.
+/// The result is a nothing-up-my-sleeve generator: its discrete logarithm is unknown.
+/// When q divides p - 1 a candidate is 1 with probability 1 / q, the search fails
+/// only for parameters without a subgroup of order q
+///
+pub fn derive_generator(
+    p: &BigUint,
+    q: &BigUint,
+    seed: &[u8],
+    index: u8,
+) -> Result<BigUint, ParameterError> {
+    let one = BigUint::from(1u32);
+    if *q <= one || *p <= *q || (p - &one) % q != BigUint::from(0u32) {
+        return Err(ParameterError::GeneratorNotFound);
+    }
+    let e = (p - &one) / q;
+    let blocks = (p.bits() + 64).div_ceil(256) as u32;
+    for count in 1..=u16::MAX {
+        let mut w = Vec::new();
+        for block in 0..blocks {
+            let mut hasher = Sha256::new();
+            hasher.update(seed);
+            hasher.update(GENERATOR_TAG);
+            hasher.update([index]);
+            hasher.update(count.to_be_bytes());
+            hasher.update(block.to_be_bytes());
+            w.extend_from_slice(&hasher.finalize());
+        }
+        let generator = (BigUint::from_bytes_be(&w) % p).modpow(&e, p);
+        if generator > one {
+            return Ok(generator);
+        }
+    }
+    Err(ParameterError::GeneratorNotFound)
 }
 
-// pub fn generate_2048bit_group_with_256bit_constants() -> ZKPProtocol {
-//     // The prime
-//     let p = BigUint::from_bytes_be(&hex::decode("87A8E61DB4B6663CFFBBD19C651959998CEEF608660DD0F25D2CEED4435E3B00E00DF8F1D61957D4FAF7DF4561B2AA3016C3D91134096FAA3BF4296D830E9A7C209E0C6497517ABD5A8A9D306BCF67ED91F9E6725B4758C022E0B1EF4275BF7B6C5BFC11D45F9088B941F54EB1E59BB8BC39A0BF12307F5C4FDB70C581B23F76B63ACAE1CAA6B7902D52526735488A0EF13C6D9A51BFA4AB3AD8347796524D8EF6A167B5A41825D967E144E5140564251CCACB83E6B486F6B3CA3F7971506026C0B857F689962856DED4010ABD0BE621C3A3960A54E710C375F26375D7014103A4B54330C198AF126116D2276E11715F693877FAD7EF09CADB094AE91E1A1597").unwrap());
+/// Index of the generator g in `derive_generator` for the generated groups
+pub const G_GENERATOR_INDEX: u8 = 0;
+/// Tag of the derivation of the Miller-Rabin bases recorded in the certificates
+const WITNESS_TAG: &[u8] = b"zkp-protocol-ex/group-certificate/witness";
 
-//     // The generator
-//     let g = BigUint::from_bytes_be(&hex::decode("3FB32C9B73134D0B2E77506660EDBD484CA7B18F21EF205407F4793A1A0BA12510DBC15077BE463FFF4FED4AAC0BB555BE3A6C1B0C6B47B1BC3773BF7E8C6F62901228F8C28CBB18A55AE31341000A650196F931C77A57F2DDF463E5E9EC144B777DE62AAAB8A8628AC376D282D6ED3864E67982428EBC831D14348F6F2F9193B5045AF2767164E1DFC967C1FB3F2E55A4BD1BFFE83B9C80D052B985D182EA0ADB2A3B7313D3FE14C8484B1E052588B9B7D2BBD2DF016199ECD06E1557CD0915B3353BBB64E0EC377FD028370DF92B52C7891428CDC67EB6184B523D1DB246C32F63078490F00EF8D647D148D47954515E2327CFEF98C582664B4C0F6CC41659").unwrap());
+///
+/// Structure of a generated group
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GroupKind {
+    /// p = k * q + 1 with a small q (FIPS 186-4 A.1.1.2)
+    Schnorr,
+    /// p = 2 * q + 1
+    SafePrime,
+}
 
-//     // The generator generates a prime-order subgroup of size:
-//     let q = BigUint::from_bytes_be(
-//         &hex::decode("8CF83642A709A097B447997640129DA299B1A47D1EB3750BA308B0FE64F5FBD3").unwrap(),
-//     );
+///
+/// Certificate of a generated group, anyone can re-run the generation from the seed
+/// with `GroupCertificate::verify` and check that it gives back the same parameters
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupCertificate {
+    pub kind: GroupKind,
+    pub bits_p: u64,
+    pub bits_q: u64,
+    /// domain parameter seed
+    #[serde(with = "hex_bytes")]
+    pub seed: Vec<u8>,
+    /// iteration of the search at which the prime p (or the safe prime pair) was found
+    pub counter: u32,
+    pub g_index: u8,
+    pub h_index: u8,
+    /// Miller-Rabin bases derived from the seed, under which p and q pass the test
+    #[serde(with = "hex_biguint_vec")]
+    pub p_witnesses: Vec<BigUint>,
+    #[serde(with = "hex_biguint_vec")]
+    pub q_witnesses: Vec<BigUint>,
+}
+
+impl ZKPProtocol {
+    ///
+    /// Generate deployment specific parameters with their certificate:
+    /// a safe-prime group if bits_q = bits_p - 1, a Schnorr group p = k * q + 1 otherwise.
+    /// The primes are searched from a random seed as in FIPS 186-4 A.1.1.2 and the
+    /// generators g and h are derived from the seed (see `derive_generator`)
+    ///
+    pub fn generate<R: RngCore + ?Sized>(
+        bits_p: u64,
+        bits_q: u64,
+        rng: &mut R,
+    ) -> Result<(ZKPProtocol, GroupCertificate), ParameterError> {
+        let kind = group_kind(bits_p, bits_q)?;
+        let seed_len = (bits_q.max(256) as usize).div_ceil(8);
+        loop {
+            let mut seed = vec![0u8; seed_len];
+            rng.fill_bytes(&mut seed);
+            if let Some((p, q, counter)) = search_primes(kind, bits_p, bits_q, &seed) {
+                let certificate = GroupCertificate {
+                    kind,
+                    bits_p,
+                    bits_q,
+                    p_witnesses: witness_bases(&seed, b"p", &p),
+                    q_witnesses: witness_bases(&seed, b"q", &q),
+                    seed,
+                    counter,
+                    g_index: G_GENERATOR_INDEX,
+                    h_index: H_GENERATOR_INDEX,
+                };
+                let g = derive_generator(&p, &q, &certificate.seed, G_GENERATOR_INDEX)?;
+                let h = derive_generator(&p, &q, &certificate.seed, H_GENERATOR_INDEX)?;
+                return Ok((ZKPProtocol::new(p, q, g, h)?, certificate));
+            }
+        }
+    }
+}
+
+impl GroupCertificate {
+    ///
+    /// Re-run the generation from the seed: p and q must be the first primes found and
+    /// found at the recorded counter, the witnesses must be the ones derived from the seed
+    /// and p, q must pass Miller-Rabin for them, and g, h must be derived from the seed
+    ///
+    pub fn verify(&self, params: &ZKPProtocol) -> Result<(), ParameterError> {
+        let mismatch = ParameterError::CertificateMismatch;
+        if group_kind(self.bits_p, self.bits_q)? != self.kind {
+            return Err(mismatch("kind"));
+        }
+        if params.p.bits() != self.bits_p || params.q.bits() != self.bits_q {
+            return Err(mismatch("sizes"));
+        }
+        if self.p_witnesses != witness_bases(&self.seed, b"p", &params.p)
+            || self.q_witnesses != witness_bases(&self.seed, b"q", &params.q)
+        {
+            return Err(mismatch("witnesses"));
+        }
+        if !miller_rabin(&params.p, &self.p_witnesses) {
+            return Err(ParameterError::PNotPrime);
+        }
+        if !miller_rabin(&params.q, &self.q_witnesses) {
+            return Err(ParameterError::QNotPrime);
+        }
+        match search_primes(self.kind, self.bits_p, self.bits_q, &self.seed) {
+            Some((p, q, counter)) if p == params.p && q == params.q && counter == self.counter => {}
+            _ => return Err(mismatch("seed and counter")),
+        }
+        if derive_generator(&params.p, &params.q, &self.seed, self.g_index)? != params.g {
+            return Err(mismatch("g"));
+        }
+        if derive_generator(&params.p, &params.q, &self.seed, self.h_index)? != params.h {
+            return Err(mismatch("h"));
+        }
+        if self.g_index == self.h_index {
+            return Err(mismatch("generator indexes"));
+        }
+        params.validate(&SecurityLevel::NONE)
+    }
+}
+
+fn group_kind(bits_p: u64, bits_q: u64) -> Result<GroupKind, ParameterError> {
+    if bits_q < 16 || bits_q >= bits_p || bits_p > MAX_P_BITS {
+        return Err(ParameterError::InvalidSizes { bits_p, bits_q });
+    }
+    if bits_q == bits_p - 1 {
+        Ok(GroupKind::SafePrime)
+    } else {
+        Ok(GroupKind::Schnorr)
+    }
+}
 
-//     // Another generator:
-//     // h = g^i, where i == 266FEA1E5C41564B777E69
-//     let i = BigUint::from_bytes_be(&hex::decode("266FEA1E5C41564B777E69").unwrap());
-//     let h = g.modpow(&i, &p);
+///
+/// Search the primes from the seed, the first candidates passing the primality tests are
+/// returned with the counter at which they were found, None if the seed must be replaced
+///
+fn search_primes(
+    kind: GroupKind,
+    bits_p: u64,
+    bits_q: u64,
+    seed: &[u8],
+) -> Option<(BigUint, BigUint, u32)> {
+    let q_blocks = bits_q.div_ceil(256);
+    match kind {
+        GroupKind::Schnorr => {
+            // q = 2^(N-1) + U + 1 - (U mod 2) with U = Hash(seed) mod 2^(N-1)
+            let q = odd_candidate(seed, 0, bits_q);
+            if !is_probable_prime(&q, MILLER_RABIN_ROUNDS) {
+                return None;
+            }
+            // X = W + 2^(L-1) and p = X - (X mod 2q - 1) so that 2q divides p - 1
+            let p_blocks = bits_p.div_ceil(256);
+            let two_q = &q << 1;
+            let min_p = BigUint::from(1u32) << (bits_p - 1);
+            for counter in 0..4 * bits_p as u32 {
+                let offset = q_blocks + counter as u64 * p_blocks;
+                let x = expand_seed(seed, offset, p_blocks) % &min_p + &min_p;
+                let c = &x % &two_q;
+                let p = x - c + 1u32;
+                if p >= min_p && is_probable_prime(&p, MILLER_RABIN_ROUNDS) {
+                    return Some((p, q, counter));
+                }
+            }
+            None
+        }
+        GroupKind::SafePrime => {
+            for counter in 0..64 * bits_p as u32 {
+                let q = odd_candidate(seed, counter as u64 * q_blocks, bits_q);
+                let p: BigUint = (&q << 1) + 1u32;
+                // cheap test of p first, most candidates q are rejected by the trial division
+                if is_probable_prime(&p, 1)
+                    && is_probable_prime(&q, MILLER_RABIN_ROUNDS)
+                    && is_probable_prime(&p, MILLER_RABIN_ROUNDS)
+                {
+                    return Some((p, q, counter));
+                }
+            }
+            None
+        }
+    }
+}
 
-//     ZKPProtocol { p, q, g, h }
-// }
+///
+/// Odd number of exactly `bits` bits from the hash of the seed
+///
+fn odd_candidate(seed: &[u8], offset: u64, bits: u64) -> BigUint {
+    let top = BigUint::from(1u32) << (bits - 1);
+    let u = expand_seed(seed, offset, bits.div_ceil(256)) % &top;
+    let parity = &u % 2u32;
+    top + u + 1u32 - parity
+}
+
+///
+/// W = Hash(seed + offset) + Hash(seed + offset + 1) * 2^256 + ... (blocks hashes),
+/// the seed is read as a big-endian integer modulo 2^seedlen as in FIPS 186-4
+///
+fn expand_seed(seed: &[u8], offset: u64, blocks: u64) -> BigUint {
+    let seed_value = BigUint::from_bytes_be(seed);
+    let modulus = BigUint::from(1u32) << (8 * seed.len());
+    let mut w = BigUint::from(0u32);
+    for j in 0..blocks {
+        let value = (&seed_value + offset + j) % &modulus;
+        let mut bytes = value.to_bytes_be();
+        // keep the length of the seed for the hash input
+        let mut padded = vec![0u8; seed.len().saturating_sub(bytes.len())];
+        padded.append(&mut bytes);
+        let v = BigUint::from_bytes_be(&Sha256::digest(&padded));
+        w += v << (256 * j);
+    }
+    w
+}
+
+///
+/// Miller-Rabin bases in [2, n - 2] derived from the seed
+///
+fn witness_bases(seed: &[u8], name: &[u8], n: &BigUint) -> Vec<BigUint> {
+    let range = n - 3u32;
+    (0..MILLER_RABIN_ROUNDS as u32)
+        .map(|i| {
+            let mut hasher = Sha256::new();
+            hasher.update(WITNESS_TAG);
+            absorb(&mut hasher, seed);
+            absorb(&mut hasher, name);
+            hasher.update(i.to_be_bytes());
+            let first = hasher.finalize();
+            let second = Sha256::digest(first);
+            let value = BigUint::from_bytes_be(&[first, second].concat());
+            value % &range + 2u32
+        })
+        .collect()
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s).map_err(serde::de::Error::custom)
+    }
+}
+
+mod hex_biguint_vec {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[BigUint], serializer: S) -> Result<S::Ok, S::Error> {
+        let encoded: Vec<String> = values.iter().map(|v| v.to_str_radix(16)).collect();
+        encoded.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<BigUint>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| {
+                BigUint::parse_bytes(s.as_bytes(), 16)
+                    .ok_or_else(|| serde::de::Error::custom("invalid hexadecimal number"))
+            })
+            .collect()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::group::PrimeOrderGroup;
+
+    fn toy_group(p: u32, q: u32, g: u32, h: u32) -> Result<ZKPProtocol, ParameterError> {
+        ZKPProtocol::new(p.into(), q.into(), g.into(), h.into())
+    }
 
     #[test]
     fn test_with_fixed_k_c() {
-        let params = ZKPProtocol {
-            p: BigUint::from(23u32),
-            q: BigUint::from(11u32),
-            g: BigUint::from(4u32),
-            h: BigUint::from(9u32),
-        };
+        let params = toy_group(23, 11, 4, 9).unwrap();
+        let scalar = |value: u32| params.scalar_from_biguint(&value.into());
 
-        let x = BigUint::from(3u32);
-        let k = BigUint::from(4u32);
+        let x = scalar(3);
+        let k = scalar(4);
 
         let (y1, y2) = params.compute_public_pair(&x);
         assert_eq!(y1, BigUint::from(18u32));
@@ -140,10 +784,10 @@ mod tests {
         assert_eq!(r1, BigUint::from(3u32));
         assert_eq!(r2, BigUint::from(6u32));
 
-        let c = BigUint::from(2u32);
+        let c = scalar(2);
 
         let s = params.compute_solution_for_challenge(&k, &c, &x);
-        assert_eq!(s, BigUint::from(9u32));
+        assert_eq!(s, scalar(9));
 
         let verified = params.verify_solution(&c, &s, &r1, &r2, &y1, &y2);
         assert!(verified);
@@ -151,25 +795,210 @@ mod tests {
 
     #[test]
     fn test_with_random_k_c() {
-        let params = ZKPProtocol {
-            p: BigUint::from(23u32),
-            q: BigUint::from(11u32),
-            g: BigUint::from(4u32),
-            h: BigUint::from(9u32),
-        };
+        let params = toy_group(23, 11, 4, 9).unwrap();
 
-        let x = BigUint::from(3u32);
-        let k = generate_random_value(&params.q);
+        let x = params.scalar_from_biguint(&BigUint::from(3u32));
+        let k = params.random_scalar();
 
         let (y1, y2) = params.compute_public_pair(&x);
         assert_eq!(y1, BigUint::from(18u32));
         assert_eq!(y2, BigUint::from(16u32));
 
         let (r1, r2) = params.compute_public_pair(&k);
-        let c = generate_random_value(&params.q);
+        let c = params.random_scalar();
         let s = params.compute_solution_for_challenge(&k, &c, &x);
 
         let verified = params.verify_solution(&c, &s, &r1, &r2, &y1, &y2);
         assert!(verified);
     }
+
+    #[test]
+    fn test_non_interactive_proof() {
+        let params = generate_1024bit_group_with_160bit_constants();
+        let x = params.random_scalar();
+        let (y1, y2) = params.compute_public_pair(&x);
+
+        let proof = params.prove_non_interactive(&x, b"login:alice");
+        assert!(params.verify_non_interactive(&proof, &y1, &y2, b"login:alice"));
+
+        // the proof is bound to the context and to the public pair
+        assert!(!params.verify_non_interactive(&proof, &y1, &y2, b"login:bob"));
+        assert!(!params.verify_non_interactive(&proof, &y2, &y1, b"login:alice"));
+
+        let forged = Proof {
+            c: proof.c.clone(),
+            s: (&proof.s + 1u32) % params.q(),
+        };
+        assert!(!params.verify_non_interactive(&forged, &y1, &y2, b"login:alice"));
+    }
+
+    #[test]
+    fn test_non_interactive_proof_serialization() {
+        let params = get_fixed_zkp_params();
+        let x = params.scalar_from_biguint(&BigUint::from(3u32));
+        let (y1, y2) = params.compute_public_pair(&x);
+
+        let proof = params.prove_non_interactive(&x, b"batch");
+        let encoded = serde_json::to_string(&proof).unwrap();
+        let decoded: Proof = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(params.verify_non_interactive(&decoded, &y1, &y2, b"batch"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let params = get_fixed_zkp_params();
+        assert_eq!(params.validate(&SecurityLevel::NONE), Ok(()));
+        assert_eq!(
+            params.validate(&SecurityLevel::BITS_80),
+            Err(ParameterError::PTooSmall {
+                bits: 5,
+                min_bits: 1024
+            })
+        );
+
+        let params = generate_1024bit_group_with_160bit_constants();
+        assert_eq!(params.validate(&SecurityLevel::BITS_80), Ok(()));
+        assert!(matches!(
+            params.validate(&SecurityLevel::BITS_112),
+            Err(ParameterError::PTooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_parameters() {
+        let with = |p, q, g, h| toy_group(p, q, g, h)?.validate(&SecurityLevel::NONE);
+
+        assert_eq!(with(21, 11, 4, 9), Err(ParameterError::PNotPrime));
+        assert_eq!(with(23, 9, 4, 9), Err(ParameterError::QNotPrime));
+        assert_eq!(with(23, 7, 4, 9), Err(ParameterError::QDoesNotDivideOrder));
+        assert_eq!(
+            with(23, 11, 1, 9),
+            Err(ParameterError::InvalidGenerator {
+                name: "g",
+                reason: "must be in ]1, p[",
+            })
+        );
+        // 5 is a generator of the whole group Z/23Z*, of order 22
+        assert_eq!(
+            with(23, 11, 4, 5),
+            Err(ParameterError::InvalidGenerator {
+                name: "h",
+                reason: "does not have order q",
+            })
+        );
+        assert_eq!(
+            with(23, 11, 4, 4),
+            Err(ParameterError::InvalidGenerator {
+                name: "h",
+                reason: "must be different from g",
+            })
+        );
+    }
+
+    #[test]
+    fn test_h_derivation() {
+        let params = generate_1024bit_group_with_160bit_constants();
+        assert!(params.verify_h_derivation(RFC5114_1024_160_H_LABEL.as_bytes()));
+        assert!(!params.verify_h_derivation(b"another label"));
+        assert_eq!(params.validate(&SecurityLevel::BITS_80), Ok(()));
+
+        // the previous h = g^i with a published i is rejected by the audit
+        let i = BigUint::from_bytes_be(&hex::decode("266FEA1E5C41564B777E69").unwrap());
+        let weak = ZKPProtocol::new(
+            params.p.clone(),
+            params.q.clone(),
+            params.g.clone(),
+            params.g.modpow(&i, &params.p),
+        )
+        .unwrap();
+        assert!(!weak.verify_h_derivation(RFC5114_1024_160_H_LABEL.as_bytes()));
+    }
+
+    #[test]
+    fn test_derive_generator_index() {
+        let params = generate_1024bit_group_with_160bit_constants();
+        let label = RFC5114_1024_160_H_LABEL.as_bytes();
+        let other = derive_generator(&params.p, &params.q, label, H_GENERATOR_INDEX + 1).unwrap();
+        assert_ne!(other, params.h);
+        assert_eq!(other.modpow(&params.q, &params.p), BigUint::from(1u32));
+
+        // without a subgroup of order q there is no generator to find: p - 2 does not
+        // divide p - 1
+        let q = &params.p - 2u32;
+        assert_eq!(
+            derive_generator(&params.p, &q, label, H_GENERATOR_INDEX),
+            Err(ParameterError::GeneratorNotFound)
+        );
+        let params = ZKPProtocol::new(params.p.clone(), q, params.g.clone(), params.h).unwrap();
+        assert!(!params.verify_h_derivation(label));
+    }
+
+    #[test]
+    fn test_generate_schnorr_group() {
+        let (params, certificate) =
+            ZKPProtocol::generate(512, 160, &mut rand::thread_rng()).unwrap();
+        assert_eq!(certificate.kind, GroupKind::Schnorr);
+        assert_eq!(params.p.bits(), 512);
+        assert_eq!(params.q.bits(), 160);
+        assert_eq!(params.validate(&SecurityLevel::NONE), Ok(()));
+        assert_eq!(certificate.verify(&params), Ok(()));
+
+        // full authentication cycle with the generated group
+        let x = params.random_scalar();
+        let (y1, y2) = params.compute_public_pair(&x);
+        let proof = params.prove_non_interactive(&x, b"generated");
+        assert!(params.verify_non_interactive(&proof, &y1, &y2, b"generated"));
+    }
+
+    #[test]
+    fn test_generate_safe_prime_group() {
+        let (params, certificate) =
+            ZKPProtocol::generate(128, 127, &mut rand::thread_rng()).unwrap();
+        assert_eq!(certificate.kind, GroupKind::SafePrime);
+        assert_eq!(params.p, (&params.q << 1) + 1u32);
+        assert_eq!(certificate.verify(&params), Ok(()));
+    }
+
+    #[test]
+    fn test_certificate_rejects_other_parameters() {
+        let mut rng = rand::thread_rng();
+        let (params, certificate) = ZKPProtocol::generate(256, 64, &mut rng).unwrap();
+        let (other, _) = ZKPProtocol::generate(256, 64, &mut rng).unwrap();
+
+        assert!(certificate.verify(&other).is_err());
+        let mut tampered = certificate.clone();
+        tampered.counter += 1;
+        assert_eq!(
+            tampered.verify(&params),
+            Err(ParameterError::CertificateMismatch("seed and counter"))
+        );
+        let swapped = ZKPProtocol::new(
+            params.p.clone(),
+            params.q.clone(),
+            params.h.clone(),
+            params.g.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            certificate.verify(&swapped),
+            Err(ParameterError::CertificateMismatch("g"))
+        );
+
+        let encoded = serde_json::to_string(&certificate).unwrap();
+        let decoded: GroupCertificate = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, certificate);
+    }
+
+    #[test]
+    fn test_generate_invalid_sizes() {
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            ZKPProtocol::generate(256, 256, &mut rng).unwrap_err(),
+            ParameterError::InvalidSizes {
+                bits_p: 256,
+                bits_q: 256
+            }
+        );
+    }
 }
@@ -1,118 +1,723 @@
-pub mod zkp_auth {
-    include!("./zkp_auth.rs");
-}
+// Async client of the authentication server for the tokio applications
+// The secrets are derived from the password, and the challenges solved, internally
 
-use curve25519_dalek::{scalar::Scalar, RistrettoPoint};
-use num_bigint::BigUint;
-use std::env;
-use zkp_auth::{
+use crate::config::DEFAULT_GROUP;
+use crate::error::error_info;
+use crate::group::PrimeOrderGroup;
+use crate::kdf::{derive_secret, generate_salt, KdfError, KdfParams};
+use crate::montgomery::ModpScalar;
+use crate::named_groups::NamedGroup;
+use crate::pedersen_elliptic_curve::{
+    derive_blinding_factor, generate_random_scalar, pedersen_setup_base_points,
+    registration_context,
+};
+use crate::session_auth::{AuthenticatedUser, BEARER_PREFIX};
+use crate::store::AuthMode;
+use crate::tls::{ClientTls, TlsError};
+use crate::zkp_auth::{
     auth_client::AuthClient, AuthenticationAnswerRequest, AuthenticationChallengeRequest,
-    CommitmentOpeningRequest, PedersenCommitmentRequest, RegisterRequest,
+    CommitmentOpeningAnswerRequest, CredentialGroup, KdfParameters, LogoutRequest,
+    PedersenCommitmentRequest, RefreshSessionRequest, RegisterRequest, RotateCredentialRequest,
+    SaltRequest, ValidateSessionRequest,
 };
-use zkp_protocol_ex::{
-    chaum_pedersen::*,
-    pedersen_elliptic_curve::pedersen_setup_base_points,
+use curve25519_dalek::Scalar;
+use std::fmt;
+use tokio::task::spawn_blocking;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+
+#[derive(Debug)]
+pub enum ClientError {
+    InvalidUrl(tonic::transport::Error),
+    Tls(TlsError),
+    Connect(tonic::transport::Error),
+    Kdf(KdfError),
+    /// Response of the server that the client cannot use, such as a malformed challenge
+    InvalidResponse(&'static str),
+    /// Error of the server, with its `ErrorInfo` reason in the details
+    Status(Status),
+    /// The derivation of the secret was cancelled by the shutdown of the runtime
+    Cancelled,
+}
+
+impl ClientError {
+    ///
+    /// gRPC code of an error of the server
+    ///
+    pub fn code(&self) -> Option<Code> {
+        match self {
+            ClientError::Status(status) => Some(status.code()),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Stable reason of an error of the server, such as `VERIFICATION_FAILED`
+    ///
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            ClientError::Status(status) => error_info(status).map(|info| info.reason),
+            _ => None,
+        }
+    }
+
+    ///
+    /// The server could not be reached: connection refused or dropped, failed TLS
+    /// handshake. A status of the transport comes without a meaningful code
+    ///
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            ClientError::Connect(_) => true,
+            ClientError::Status(status) => {
+                std::error::Error::source(status)
+                    .is_some_and(|source| source.is::<tonic::transport::Error>())
+                    || status.code() == Code::Unavailable
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::InvalidUrl(e) => write!(f, "invalid server URL: {}", e),
+            ClientError::Tls(e) => write!(f, "{}", e),
+            ClientError::Connect(e) => write!(f, "could not connect to the server: {}", e),
+            ClientError::Kdf(e) => write!(f, "cannot derive the secret: {}", e),
+            ClientError::InvalidResponse(reason) => {
+                write!(f, "invalid response of the server: {}", reason)
+            }
+            ClientError::Status(status) => f.write_str(status.message()),
+            ClientError::Cancelled => write!(f, "the derivation of the secret was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::InvalidUrl(e) => Some(e),
+            ClientError::Tls(e) => Some(e),
+            ClientError::Connect(e) => Some(e),
+            ClientError::Kdf(e) => Some(e),
+            ClientError::InvalidResponse(_) | ClientError::Status(_) | ClientError::Cancelled => {
+                None
+            }
+        }
+    }
+}
+
+impl From<Status> for ClientError {
+    fn from(status: Status) -> Self {
+        ClientError::Status(status)
+    }
+}
+
+impl From<KdfError> for ClientError {
+    fn from(e: KdfError) -> Self {
+        ClientError::Kdf(e)
+    }
+}
+
+///
+/// Session opened by a login
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub id: String,
+    pub user: String,
+    pub mode: AuthMode,
+}
+
+impl Session {
+    ///
+    /// `authorization` metadata value of the requests to the services behind `SessionAuthLayer`
+    ///
+    pub fn bearer(&self) -> String {
+        format!("{}{}", BEARER_PREFIX, self.id)
+    }
+}
+
+/// Most expensive KDF parameters accepted from the server by default: 256 MiB, 8 passes
+/// and 16 lanes, four times the cost of `KdfParams::default`
+pub const DEFAULT_MAX_KDF_PARAMS: KdfParams = KdfParams {
+    memory_kib: 256 * 1024,
+    iterations: 8,
+    parallelism: 16,
 };
 
-enum AuthType {
-    Exponentiation,
-    EllipticCurve,
+///
+/// Secrets of a password with the salts and KDF parameters of a credential
+///
+struct Secrets {
+    x: ModpScalar,
+    ec_x: Scalar,
+    blinding: Scalar,
+}
+
+impl Secrets {
+    fn derive(
+        group: NamedGroup,
+        secret: &[u8],
+        salt: &[u8],
+        blinding_salt: &[u8],
+        params: &KdfParams,
+    ) -> Result<Self, KdfError> {
+        let ec_x = derive_secret(&pedersen_setup_base_points(), secret, salt, params)?;
+        Ok(Secrets {
+            x: derive_secret(&group.params(), secret, salt, params)?,
+            ec_x,
+            blinding: derive_blinding_factor(&ec_x, blinding_salt),
+        })
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
-    let url = format!("{}", &args[2].to_string());
-    println!("URL {}", url.clone());
-    let mut client = AuthClient::connect(url)
+///
+/// Salts and KDF parameters registered for a user, with the parameters already bounded
+///
+struct Registration {
+    salt: Vec<u8>,
+    blinding_salt: Vec<u8>,
+    params: KdfParams,
+    rotation_nonce: Vec<u8>,
+}
+
+///
+/// Runs a derivation on the blocking threads of the runtime: Argon2id takes long enough to
+/// stall the other tasks of a worker thread
+///
+async fn spawn_derivation<T, F>(derive: F) -> Result<T, ClientError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, KdfError> + Send + 'static,
+{
+    match spawn_blocking(derive).await {
+        Ok(result) => Ok(result?),
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(ClientError::Cancelled),
+    }
+}
+
+async fn elliptic_curve_secret(
+    secret: &[u8],
+    registration: &Registration,
+) -> Result<Scalar, ClientError> {
+    let (secret, salt, params) = (
+        secret.to_vec(),
+        registration.salt.clone(),
+        registration.params,
+    );
+    spawn_derivation(move || derive_secret(&pedersen_setup_base_points(), &secret, &salt, &params))
         .await
-        .map_err(|e| e)
-        .expect("Could not connect to the server");
-
-    // For big number (not tested yet)
-    // let protocol = generate_1024bit_group_with_160bit_constants();
-    let protocol = get_fixed_zkp_params();
-    let user = "USER_NAME_TO_ENTER".to_string();
-    let x = 123624374743u64;
-    let x_password = BigUint::from(x);
-    let (y1, y2) = protocol.compute_public_pair(&x_password);
-
-    println!("✅ Client registers");
-    let request_register = tonic::Request::new(RegisterRequest {
-        user: user.clone(),
+}
+
+///
+/// Credential of a new secret with fresh salts
+///
+fn new_credential(
+    group: NamedGroup,
+    kdf_params: &KdfParams,
+    user: &str,
+    secret: &[u8],
+) -> Result<RegisterRequest, KdfError> {
+    let salt = generate_salt();
+    let blinding_salt = generate_salt();
+    let secrets = Secrets::derive(group, secret, &salt, &blinding_salt, kdf_params)?;
+    let zkpelliptic = pedersen_setup_base_points();
+    let (y1, y2) = group.params().compute_public_pair(&secrets.x);
+    let (ec_y1, ec_y2) = zkpelliptic.compute_public_pair(&secrets.ec_x);
+    // long-term commitment, the blinding factor is re-derived from the secret to open it
+    let commitment = zkpelliptic.pedersen_commit(secrets.ec_x, secrets.blinding);
+    Ok(RegisterRequest {
+        user: user.to_string(),
         y1: y1.to_bytes_be(),
         y2: y2.to_bytes_be(),
-    });
-    let _ = client.register(request_register).await?;
-
-    // Choosing between Exponentiation or Elliptic curve authentication (uncomment one)
-    // let authentication_type = AuthType::EllipticCurve;
-    let authentication_type = AuthType::Exponentiation;
-
-    match authentication_type {
-        AuthType::Exponentiation => {
-            println!("Authentication type: exponentiation");
-            println!("✅ Client requests a challenge");
-            let k = generate_random_value(&protocol.q);
-            let (r1, r2) = protocol.compute_public_pair(&k);
-
-            let request_challenge = tonic::Request::new(AuthenticationChallengeRequest {
-                user: user.clone(),
+        ec_y1: ec_y1.compress().to_bytes().to_vec(),
+        ec_y2: ec_y2.compress().to_bytes().to_vec(),
+        pedersen_commitment: commitment.compress().to_bytes().to_vec(),
+        blinding_salt,
+        salt,
+        kdf_parameters: Some(KdfParameters {
+            memory_kib: kdf_params.memory_kib,
+            iterations: kdf_params.iterations,
+            parallelism: kdf_params.parallelism,
+        }),
+    })
+}
+
+/// Domain separation tag of the rotation proofs
+const ROTATION_DOMAIN: &[u8] = b"zkp-protocol-ex/rotate/v1";
+
+///
+/// Context of the proof of a credential rotation in `group`: the new credential is bound
+/// to the proof so that it cannot be swapped by a man in the middle, and the rotation_nonce
+/// of the registered credential so that the proof cannot be replayed once it is replaced
+///
+pub fn rotation_context(
+    credential: &RegisterRequest,
+    group: CredentialGroup,
+    rotation_nonce: &[u8],
+) -> Vec<u8> {
+    let kdf_params = credential
+        .kdf_parameters
+        .as_ref()
+        .map(|params| {
+            KdfParams {
+                memory_kib: params.memory_kib,
+                iterations: params.iterations,
+                parallelism: params.parallelism,
+            }
+            .to_bytes()
+        })
+        .unwrap_or_default();
+    registration_context(
+        &credential.user,
+        &[
+            ROTATION_DOMAIN,
+            group.as_str_name().as_bytes(),
+            rotation_nonce,
+            &credential.y1,
+            &credential.y2,
+            &credential.ec_y1,
+            &credential.ec_y2,
+            &credential.pedersen_commitment,
+            &credential.blinding_salt,
+            &credential.salt,
+            &kdf_params,
+        ],
+    )
+}
+
+///
+/// Client of the `Auth` service. The exponentiation group must be the one of the server,
+/// the new credentials are derived with `kdf_params` and the parameters of the server
+/// above `max_kdf_params` are rejected before any derivation
+///
+#[derive(Debug, Clone)]
+pub struct ZkpAuthClient {
+    client: AuthClient<Channel>,
+    group: NamedGroup,
+    kdf_params: KdfParams,
+    max_kdf_params: KdfParams,
+}
+
+impl ZkpAuthClient {
+    pub fn new(channel: Channel) -> Self {
+        ZkpAuthClient {
+            client: AuthClient::new(channel),
+            group: DEFAULT_GROUP,
+            kdf_params: KdfParams::default(),
+            max_kdf_params: DEFAULT_MAX_KDF_PARAMS,
+        }
+    }
+
+    ///
+    /// Connects to a plaintext `http://` URL
+    ///
+    pub async fn connect(url: impl Into<String>) -> Result<Self, ClientError> {
+        let endpoint = Endpoint::from_shared(url.into()).map_err(ClientError::InvalidUrl)?;
+        let channel = endpoint.connect().await.map_err(ClientError::Connect)?;
+        Ok(ZkpAuthClient::new(channel))
+    }
+
+    ///
+    /// Connects to an `https://` URL, verifying the server against the CA of `tls`
+    ///
+    pub async fn connect_tls(url: impl Into<String>, tls: &ClientTls) -> Result<Self, ClientError> {
+        let endpoint = Endpoint::from_shared(url.into())
+            .map_err(ClientError::InvalidUrl)?
+            .tls_config(tls.client_tls_config().map_err(ClientError::Tls)?)
+            .map_err(ClientError::Connect)?;
+        let channel = endpoint.connect().await.map_err(ClientError::Connect)?;
+        Ok(ZkpAuthClient::new(channel))
+    }
+
+    pub fn with_group(mut self, group: NamedGroup) -> Self {
+        self.group = group;
+        self
+    }
+
+    pub fn with_kdf_params(mut self, kdf_params: KdfParams) -> Self {
+        self.kdf_params = kdf_params;
+        self
+    }
+
+    pub fn with_max_kdf_params(mut self, max_kdf_params: KdfParams) -> Self {
+        self.max_kdf_params = max_kdf_params;
+        self
+    }
+
+    ///
+    /// Credential of `secret` derived on the blocking threads
+    ///
+    async fn derive_credential(
+        &self,
+        user: &str,
+        secret: &[u8],
+    ) -> Result<RegisterRequest, ClientError> {
+        let (group, kdf_params) = (self.group, self.kdf_params);
+        let (user, secret) = (user.to_string(), secret.to_vec());
+        spawn_derivation(move || new_credential(group, &kdf_params, &user, &secret)).await
+    }
+
+    ///
+    /// Registers a new user with the credential derived from its secret
+    ///
+    pub async fn register(
+        &mut self,
+        user: &str,
+        secret: impl AsRef<[u8]>,
+    ) -> Result<(), ClientError> {
+        let credential = self.derive_credential(user, secret.as_ref()).await?;
+        self.client.register(credential).await?;
+        Ok(())
+    }
+
+    ///
+    /// Replaces the credential of the user with the one of `new_secret`, proving the
+    /// knowledge of the current secret in the exponentiation group, or over the elliptic
+    /// curve if the server disabled the exponentiation mode
+    ///
+    pub async fn rotate(
+        &mut self,
+        user: &str,
+        current_secret: impl AsRef<[u8]>,
+        new_secret: impl AsRef<[u8]>,
+    ) -> Result<(), ClientError> {
+        let registration = self.registration(user).await?;
+        let credential = self.derive_credential(user, new_secret.as_ref()).await?;
+        let current_secret = current_secret.as_ref();
+        match self
+            .rotate_with(
+                CredentialGroup::Exponentiation,
+                current_secret,
+                &registration,
+                credential.clone(),
+            )
+            .await
+        {
+            Err(e) if e.reason().as_deref() == Some("MODE_DISABLED") => {
+                self.rotate_with(
+                    CredentialGroup::EllipticCurve,
+                    current_secret,
+                    &registration,
+                    credential,
+                )
+                .await
+            }
+            result => result,
+        }
+    }
+
+    ///
+    /// Rotation to `credential` with a proof of the current secret in `group`
+    ///
+    async fn rotate_with(
+        &mut self,
+        group: CredentialGroup,
+        current_secret: &[u8],
+        registration: &Registration,
+        credential: RegisterRequest,
+    ) -> Result<(), ClientError> {
+        let context = rotation_context(&credential, group, &registration.rotation_nonce);
+        let (c, s) = match group {
+            CredentialGroup::Exponentiation => {
+                let x = self
+                    .exponentiation_secret(current_secret, registration)
+                    .await?;
+                let proof = self.group.params().prove_non_interactive(&x, &context);
+                (proof.c.to_bytes_be(), proof.s.to_bytes_be())
+            }
+            CredentialGroup::EllipticCurve => {
+                let ec_x = elliptic_curve_secret(current_secret, registration).await?;
+                let (c, s) = pedersen_setup_base_points().prove_non_interactive(&ec_x, &context);
+                (c.to_bytes().to_vec(), s.to_bytes().to_vec())
+            }
+        };
+        self.client
+            .rotate_credential(RotateCredentialRequest {
+                credential: Some(credential),
+                group: group as i32,
+                c,
+                s,
+            })
+            .await?;
+        Ok(())
+    }
+
+    ///
+    /// Logs in with a proof of knowledge of the secret in the given mode
+    ///
+    pub async fn login(
+        &mut self,
+        user: &str,
+        secret: impl AsRef<[u8]>,
+        mode: AuthMode,
+    ) -> Result<Session, ClientError> {
+        let registration = self.registration(user).await?;
+        // only the secret of the mode is derived
+        let id = match mode {
+            AuthMode::Exponentiation => {
+                let x = self
+                    .exponentiation_secret(secret.as_ref(), &registration)
+                    .await?;
+                self.prove_exponentiation(user, &x).await?
+            }
+            AuthMode::EllipticCurveChaumPedersen => {
+                let ec_x = elliptic_curve_secret(secret.as_ref(), &registration).await?;
+                self.prove_elliptic_curve(user, &ec_x).await?
+            }
+            AuthMode::PedersenOpening => {
+                let ec_x = elliptic_curve_secret(secret.as_ref(), &registration).await?;
+                let blinding = derive_blinding_factor(&ec_x, &registration.blinding_salt);
+                self.prove_opening(user, &ec_x, &blinding).await?
+            }
+        };
+        Ok(Session {
+            id,
+            user: user.to_string(),
+            mode,
+        })
+    }
+
+    ///
+    /// User, expiry and client certificate subject of a session, which restarts its
+    /// idle timeout
+    ///
+    pub async fn validate(&mut self, session_id: &str) -> Result<AuthenticatedUser, ClientError> {
+        let response = self
+            .client
+            .validate_session(ValidateSessionRequest {
+                session_id: session_id.to_string(),
+                client_fingerprint: String::new(),
+            })
+            .await?
+            .into_inner();
+        Ok(response.into())
+    }
+
+    ///
+    /// New session replacing `session`, which is revoked
+    ///
+    pub async fn refresh(&mut self, session: &Session) -> Result<Session, ClientError> {
+        let response = self
+            .client
+            .refresh_session(RefreshSessionRequest {
+                session_id: session.id.clone(),
+            })
+            .await?
+            .into_inner();
+        Ok(Session {
+            id: response.session_id,
+            ..session.clone()
+        })
+    }
+
+    pub async fn logout(&mut self, session_id: &str) -> Result<(), ClientError> {
+        self.revoke(session_id, false).await
+    }
+
+    ///
+    /// Revokes every session of the user of `session_id`
+    ///
+    pub async fn logout_all(&mut self, session_id: &str) -> Result<(), ClientError> {
+        self.revoke(session_id, true).await
+    }
+
+    async fn revoke(&mut self, session_id: &str, all_sessions: bool) -> Result<(), ClientError> {
+        self.client
+            .logout(LogoutRequest {
+                session_id: session_id.to_string(),
+                all_sessions,
+            })
+            .await?;
+        Ok(())
+    }
+
+    ///
+    /// Salts and KDF parameters registered for the user
+    ///
+    async fn registration(&mut self, user: &str) -> Result<Registration, ClientError> {
+        let response = self
+            .client
+            .get_salt(SaltRequest {
+                user: user.to_string(),
+            })
+            .await?
+            .into_inner();
+        let params = response
+            .kdf_parameters
+            .ok_or(ClientError::InvalidResponse("missing KDF parameters"))?;
+        let params = KdfParams {
+            memory_kib: params.memory_kib,
+            iterations: params.iterations,
+            parallelism: params.parallelism,
+        };
+        // the costs come from the server, they are bounded before the derivation
+        params.validate_within(&self.max_kdf_params)?;
+        Ok(Registration {
+            salt: response.salt,
+            blinding_salt: response.blinding_salt,
+            params,
+            rotation_nonce: response.rotation_nonce,
+        })
+    }
+
+    async fn exponentiation_secret(
+        &self,
+        secret: &[u8],
+        registration: &Registration,
+    ) -> Result<ModpScalar, ClientError> {
+        let group = self.group;
+        let (secret, salt, params) = (
+            secret.to_vec(),
+            registration.salt.clone(),
+            registration.params,
+        );
+        spawn_derivation(move || derive_secret(&group.params(), &secret, &salt, &params)).await
+    }
+
+    async fn prove_exponentiation(
+        &mut self,
+        user: &str,
+        x: &ModpScalar,
+    ) -> Result<String, ClientError> {
+        let protocol = self.group.params();
+        let k = protocol.random_scalar();
+        let (r1, r2) = protocol.compute_public_pair(&k);
+        let challenge = self
+            .client
+            .create_authentication_challenge(AuthenticationChallengeRequest {
+                user: user.to_string(),
                 r1: r1.to_bytes_be(),
                 r2: r2.to_bytes_be(),
-            });
-            let challenge_response = client
-                .create_authentication_challenge(request_challenge)
-                .await?;
+            })
+            .await?
+            .into_inner();
+        let c = protocol
+            .decode_scalar(&challenge.c)
+            .map_err(|_| ClientError::InvalidResponse("malformed challenge"))?;
+        let s = protocol.compute_solution_for_challenge(&k, &c, x);
+        let response = self
+            .client
+            .verify_authentication(AuthenticationAnswerRequest {
+                auth_id: challenge.auth_id,
+                s: protocol.encode_scalar(&s),
+            })
+            .await?;
+        Ok(response.into_inner().session_id)
+    }
 
-            let challenge = challenge_response.into_inner();
-            let c = BigUint::from_bytes_be(&challenge.c);
-            let s = protocol.compute_solution_for_challenge(&k, &c, &x_password);
+    async fn prove_elliptic_curve(
+        &mut self,
+        user: &str,
+        x: &Scalar,
+    ) -> Result<String, ClientError> {
+        let zkpelliptic = pedersen_setup_base_points();
+        let k = generate_random_scalar();
+        let (r1, r2) = zkpelliptic.compute_public_pair(&k);
+        let challenge = self
+            .client
+            .create_elliptic_curve_authentication_challenge(AuthenticationChallengeRequest {
+                user: user.to_string(),
+                r1: r1.compress().to_bytes().to_vec(),
+                r2: r2.compress().to_bytes().to_vec(),
+            })
+            .await?
+            .into_inner();
+        let c = zkpelliptic
+            .decode_scalar(&challenge.c)
+            .map_err(|_| ClientError::InvalidResponse("malformed challenge"))?;
+        let s = zkpelliptic.compute_solution_for_challenge(&k, &c, x);
+        let response = self
+            .client
+            .verify_elliptic_curve_authentication(AuthenticationAnswerRequest {
+                auth_id: challenge.auth_id,
+                s: s.to_bytes().to_vec(),
+            })
+            .await?;
+        Ok(response.into_inner().session_id)
+    }
 
-            println!("✅ Client sends the solution to the challenge");
-            let request_auth = tonic::Request::new(AuthenticationAnswerRequest {
+    async fn prove_opening(
+        &mut self,
+        user: &str,
+        m: &Scalar,
+        r: &Scalar,
+    ) -> Result<String, ClientError> {
+        let zkpelliptic = pedersen_setup_base_points();
+        let (t, a, b) = zkpelliptic.commit_opening_nonces();
+        let challenge = self
+            .client
+            .send_pedersen_commitment(PedersenCommitmentRequest {
+                user: user.to_string(),
+                t: t.compress().to_bytes().to_vec(),
+            })
+            .await?
+            .into_inner();
+        let c = zkpelliptic
+            .decode_scalar(&challenge.c)
+            .map_err(|_| ClientError::InvalidResponse("malformed challenge"))?;
+        let (s1, s2) = zkpelliptic.compute_opening_solution(&a, &b, &c, m, r);
+        let response = self
+            .client
+            .open_commitment(CommitmentOpeningAnswerRequest {
                 auth_id: challenge.auth_id,
-                s: s.to_bytes_be(),
-            });
-            let auth_response = client.verify_authentication(request_auth).await?;
+                s1: s1.to_bytes().to_vec(),
+                s2: s2.to_bytes().to_vec(),
+            })
+            .await?;
+        Ok(response.into_inner().session_id)
+    }
+}
 
-            println!("✅ Session ID {:#?}", auth_response.into_inner().session_id);
-        }
-        AuthType::EllipticCurve => {
-            println!("Authentication type: elliptic curve");
-            println!("✅ Client sends the commitment");
-            let x_password = Scalar::from(x);
-            let mut zkpelliptic = pedersen_setup_base_points();
-            let (commitment, blinding_factor, secret) = zkpelliptic.commit(x_password);
-            // println!("commitment client {:#?}",  commitment);
-            let compressed_commitment = RistrettoPoint::compress(&commitment).to_bytes().to_vec();
-            let request_set_commitment = tonic::Request::new(PedersenCommitmentRequest {
-                user: user.clone(),
-                compressed_commitment: compressed_commitment.clone(),
-            });
-            // println!("{:#?}", compressed_commitment.clone());
-            let set_commitment_response = client
-                .send_pedersen_commitment(request_set_commitment)
-                .await?;
-            let commitment_response = set_commitment_response.into_inner();
-
-            println!("✅ Client opens the commitment");
-            let request_commitment_opening = tonic::Request::new(CommitmentOpeningRequest {
-                auth_id: commitment_response.auth_id,
-                r: blinding_factor.to_bytes().to_vec(),
-                m: secret.to_bytes().to_vec(),
-            });
-            // println!("blinding {:#?}",  blinding_factor);
-            // println!("secret {:#?}",  secret);
-            let commitment_opening_response =
-                client.open_commitment(request_commitment_opening).await?;
-
-            println!(
-                "✅ Session ID {:#?}",
-                commitment_opening_response.into_inner().session_id
-            );
-        }
-        _ => println!("Unknown authentication type"),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ZkpError;
+
+    #[test]
+    fn test_credential() {
+        let group = NamedGroup::Rfc5114Modp2048Q224;
+        let params = KdfParams::INSECURE_FAST;
+        let credential = new_credential(group, &params, "alice", b"secret").unwrap();
+        let secrets = Secrets::derive(
+            group,
+            b"secret",
+            &credential.salt,
+            &credential.blinding_salt,
+            &params,
+        )
+        .unwrap();
+        let (y1, y2) = group.params().compute_public_pair(&secrets.x);
+        assert_eq!(credential.y1, y1.to_bytes_be());
+        assert_eq!(credential.y2, y2.to_bytes_be());
+        let commitment =
+            pedersen_setup_base_points().pedersen_commit(secrets.ec_x, secrets.blinding);
+        assert_eq!(
+            credential.pedersen_commitment,
+            commitment.compress().to_bytes()
+        );
+        // fresh salts for every credential
+        let other = new_credential(group, &params, "alice", b"secret").unwrap();
+        assert_ne!(other.salt, credential.salt);
+        assert_ne!(other.blinding_salt, credential.blinding_salt);
+        assert_ne!(other.pedersen_commitment, credential.pedersen_commitment);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = ClientError::from(Status::from(ZkpError::UnknownUser("alice".to_string())));
+        assert_eq!(error.code(), Some(Code::NotFound));
+        assert_eq!(error.reason().as_deref(), Some("UNKNOWN_USER"));
+        assert!(!error.is_unreachable());
+        assert!(ClientError::from(Status::unavailable("down")).is_unreachable());
+        let error = ClientError::InvalidResponse("malformed challenge");
+        assert_eq!(error.code(), None);
+        assert_eq!(
+            error.to_string(),
+            "invalid response of the server: malformed challenge"
+        );
     }
-    Ok(())
 }
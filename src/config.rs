@@ -0,0 +1,648 @@
+// Configuration of the server, layered from the lowest to the highest priority:
+// the defaults, a TOML file, the environment variables and the command line flags
+
+use crate::chaum_pedersen::SecurityLevel;
+use crate::named_groups::NamedGroup;
+use crate::store::{AuthMode, SessionTimeouts, StoreBackend};
+use crate::token::SigningKey;
+use clap::{ArgAction, Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Group of the exponentiation authentication, the client must use the same one
+pub const DEFAULT_GROUP: NamedGroup = NamedGroup::Rfc5114Modp2048Q256;
+/// Time to answer a challenge, the auth_id is rejected after it
+pub const DEFAULT_ATTEMPT_TTL: Duration = Duration::from_secs(60);
+/// A session expires when it is not used for the idle timeout, or at the latest
+/// after the absolute timeout since the login
+pub const DEFAULT_SESSION_TIMEOUTS: SessionTimeouts = SessionTimeouts {
+    idle: Duration::from_secs(15 * 60),
+    absolute: Duration::from_secs(8 * 60 * 60),
+};
+/// Period of the eviction of the expired attempts and sessions
+pub const DEFAULT_SWEEP_PERIOD: Duration = Duration::from_secs(30);
+/// Security level in bits required of the group, see `SecurityLevel`
+pub const DEFAULT_MIN_SECURITY_LEVEL: u32 = 112;
+/// Environment variables of the flags, by increasing priority: `USER_STORE`,
+/// `SESSION_TOKENS` and `TOKEN_SIGNING_KEY` are the names of the earlier versions,
+/// still read but overridden by the `ZKP_` ones
+pub const ENV_VARS: &[(&str, &str)] = &[
+    ("ZKP_CONFIG", "config"),
+    ("SERVER_HOST", "host"),
+    ("SERVER_PORT", "port"),
+    ("ZKP_GROUP", "group"),
+    ("ZKP_MIN_SECURITY_LEVEL", "min-security-level"),
+    ("ZKP_MODES", "modes"),
+    ("ZKP_ATTEMPT_TTL", "attempt-ttl"),
+    ("ZKP_SESSION_IDLE_TIMEOUT", "session-idle-timeout"),
+    ("ZKP_SESSION_ABSOLUTE_TIMEOUT", "session-absolute-timeout"),
+    ("ZKP_SWEEP_PERIOD", "sweep-period"),
+    ("USER_STORE", "store"),
+    ("ZKP_STORE", "store"),
+    ("SESSION_TOKENS", "session-tokens"),
+    ("ZKP_SESSION_TOKENS", "session-tokens"),
+    ("TOKEN_SIGNING_KEY", "token-signing-key"),
+    ("ZKP_TOKEN_SIGNING_KEY", "token-signing-key"),
+    ("ZKP_TLS_CERT", "tls-cert"),
+    ("ZKP_TLS_KEY", "tls-key"),
+    ("ZKP_TLS_CLIENT_CA", "tls-client-ca"),
+    ("RUST_LOG", "log-level"),
+];
+/// Written in place of the secret of the signing key by `--print-config`
+const REDACTED: &str = "<redacted>";
+
+///
+/// Form of the session_ids issued by the server, see `SessionTokens` of the server
+///
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionTokenKind {
+    #[default]
+    Opaque,
+    Signed,
+}
+
+///
+/// Certificate and private key of the server (PEM), and the CA of the client
+/// certificates when they are required
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.cert.is_some()
+    }
+}
+
+///
+/// Effective configuration of the server, the durations are in seconds
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: IpAddr,
+    pub port: u16,
+    #[serde(with = "text")]
+    pub group: NamedGroup,
+    /// 80, 112 or 128, the group must be large enough for it
+    pub min_security_level: u32,
+    #[serde(with = "text_list")]
+    pub modes: Vec<AuthMode>,
+    pub attempt_ttl_secs: u64,
+    pub session_idle_timeout_secs: u64,
+    pub session_absolute_timeout_secs: u64,
+    pub sweep_period_secs: u64,
+    #[serde(with = "text")]
+    pub store: StoreBackend,
+    pub session_tokens: SessionTokenKind,
+    /// `<kid>:<hex>`, generated at startup when the tokens are signed and it is unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_signing_key: Option<String>,
+    /// Filter of env_logger, such as `info` or `zkp_protocol_ex=debug`
+    pub log_level: String,
+    pub tls: TlsConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 8080,
+            group: DEFAULT_GROUP,
+            min_security_level: DEFAULT_MIN_SECURITY_LEVEL,
+            modes: AuthMode::ALL.to_vec(),
+            attempt_ttl_secs: DEFAULT_ATTEMPT_TTL.as_secs(),
+            session_idle_timeout_secs: DEFAULT_SESSION_TIMEOUTS.idle.as_secs(),
+            session_absolute_timeout_secs: DEFAULT_SESSION_TIMEOUTS.absolute.as_secs(),
+            sweep_period_secs: DEFAULT_SWEEP_PERIOD.as_secs(),
+            store: StoreBackend::Memory,
+            session_tokens: SessionTokenKind::Opaque,
+            token_signing_key: None,
+            log_level: "info".to_string(),
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+///
+/// Command line of the server, every flag but `--config` and `--print-config` overrides
+/// a key of the configuration file, and every flag but `--print-config` can also be set
+/// by its environment variable of `ENV_VARS`
+///
+#[derive(Debug, Default, Parser)]
+#[command(
+    name = "server",
+    version,
+    about = "Zero-knowledge proof authentication server",
+    args_override_self = true
+)]
+pub struct ServerArgs {
+    /// TOML configuration file
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+    /// Print the effective configuration as TOML and exit
+    #[arg(long)]
+    pub print_config: bool,
+    /// Listen address
+    #[arg(long)]
+    pub host: Option<IpAddr>,
+    /// Listen port
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Named group of the exponentiation authentication, such as rfc5114-2048-256
+    #[arg(long)]
+    pub group: Option<NamedGroup>,
+    /// Security level in bits required of the group: 80, 112 or 128
+    #[arg(long)]
+    pub min_security_level: Option<u32>,
+    /// Enabled authentication modes, comma separated, the last occurrence replaces the others
+    #[arg(long, value_delimiter = ',', action = ArgAction::Set)]
+    pub modes: Option<Vec<AuthMode>>,
+    /// Seconds to answer a challenge
+    #[arg(long)]
+    pub attempt_ttl: Option<u64>,
+    /// Seconds after which an unused session expires
+    #[arg(long)]
+    pub session_idle_timeout: Option<u64>,
+    /// Seconds after the login at which a session expires
+    #[arg(long)]
+    pub session_absolute_timeout: Option<u64>,
+    /// Seconds between two evictions of the expired attempts and sessions
+    #[arg(long)]
+    pub sweep_period: Option<u64>,
+    /// User store, memory or sqlite:<path>
+    #[arg(long)]
+    pub store: Option<StoreBackend>,
+    /// Form of the session_ids
+    #[arg(long)]
+    pub session_tokens: Option<SessionTokenKind>,
+    /// Signing key of the session tokens, <kid>:<hex>
+    #[arg(long)]
+    pub token_signing_key: Option<String>,
+    /// Certificate of the server (PEM)
+    #[arg(long)]
+    pub tls_cert: Option<PathBuf>,
+    /// Private key of the server (PEM)
+    #[arg(long)]
+    pub tls_key: Option<PathBuf>,
+    /// CA of the client certificates (PEM), required when set
+    #[arg(long)]
+    pub tls_client_ca: Option<PathBuf>,
+    /// Log filter, such as info or zkp_protocol_ex=debug
+    #[arg(long)]
+    pub log_level: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "invalid {}: {}", path.display(), e),
+            ConfigError::Invalid(reason) => write!(f, "invalid configuration: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read(_, e) => Some(e),
+            ConfigError::Parse(_, e) => Some(e),
+            ConfigError::Invalid(_) => None,
+        }
+    }
+}
+
+impl ServerArgs {
+    ///
+    /// Flags of `args` over the environment variables of `ENV_VARS` found in `vars`,
+    /// the variables are passed as the flags preceding the command line
+    ///
+    pub fn try_parse_with_env<I, T, V>(args: I, vars: V) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+        V: IntoIterator<Item = (OsString, OsString)>,
+    {
+        let vars: HashMap<OsString, OsString> = vars.into_iter().collect();
+        let mut args = args.into_iter().map(Into::into);
+        let mut merged: Vec<OsString> = args.next().into_iter().collect();
+        for (name, flag) in ENV_VARS {
+            if let Some(value) = vars.get(OsString::from(name).as_os_str()) {
+                let mut arg = OsString::from(format!("--{}=", flag));
+                arg.push(value);
+                merged.push(arg);
+            }
+        }
+        merged.extend(args);
+        ServerArgs::try_parse_from(merged)
+    }
+
+    ///
+    /// Command line and environment of the process, exits on an error as `parse` does
+    ///
+    pub fn parse_with_env() -> Self {
+        ServerArgs::try_parse_with_env(std::env::args_os(), std::env::vars_os())
+            .unwrap_or_else(|e| e.exit())
+    }
+}
+
+impl ServerConfig {
+    ///
+    /// Defaults, overridden by the file of `--config` if any, then by the environment
+    /// variables and the flags already merged into `args` by `try_parse_with_env`
+    ///
+    pub fn load(args: &ServerArgs) -> Result<Self, ConfigError> {
+        let mut config = match &args.config {
+            Some(path) => ServerConfig::from_file(path)?,
+            None => ServerConfig::default(),
+        };
+        config.apply(args);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.into(), e))?;
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(path.into(), e))
+    }
+
+    pub fn apply(&mut self, args: &ServerArgs) {
+        fn set<T: Clone>(value: &mut T, arg: &Option<T>) {
+            if let Some(arg) = arg {
+                *value = arg.clone();
+            }
+        }
+        set(&mut self.host, &args.host);
+        set(&mut self.port, &args.port);
+        set(&mut self.group, &args.group);
+        set(&mut self.min_security_level, &args.min_security_level);
+        set(&mut self.modes, &args.modes);
+        set(&mut self.attempt_ttl_secs, &args.attempt_ttl);
+        set(
+            &mut self.session_idle_timeout_secs,
+            &args.session_idle_timeout,
+        );
+        set(
+            &mut self.session_absolute_timeout_secs,
+            &args.session_absolute_timeout,
+        );
+        set(&mut self.sweep_period_secs, &args.sweep_period);
+        set(&mut self.store, &args.store);
+        set(&mut self.session_tokens, &args.session_tokens);
+        if args.token_signing_key.is_some() {
+            self.token_signing_key = args.token_signing_key.clone();
+        }
+        if args.tls_cert.is_some() {
+            self.tls.cert = args.tls_cert.clone();
+        }
+        if args.tls_key.is_some() {
+            self.tls.key = args.tls_key.clone();
+        }
+        if args.tls_client_ca.is_some() {
+            self.tls.client_ca = args.tls_client_ca.clone();
+        }
+        set(&mut self.log_level, &args.log_level);
+    }
+
+    ///
+    /// Checks the values that the types alone do not constrain
+    ///
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |reason: &str| Err(ConfigError::Invalid(reason.to_string()));
+        if self.modes.is_empty() {
+            return invalid("no authentication mode is enabled");
+        }
+        self.security_level()?;
+        if self.attempt_ttl_secs == 0
+            || self.session_idle_timeout_secs == 0
+            || self.session_absolute_timeout_secs == 0
+            || self.sweep_period_secs == 0
+        {
+            return invalid("the TTL, the timeouts and the sweep period must be positive");
+        }
+        if self.session_idle_timeout_secs > self.session_absolute_timeout_secs {
+            return invalid("the session idle timeout is longer than the absolute timeout");
+        }
+        match (self.session_tokens, &self.token_signing_key) {
+            (SessionTokenKind::Opaque, Some(_)) => {
+                return invalid("token_signing_key is set but the session tokens are opaque")
+            }
+            (SessionTokenKind::Signed, Some(key)) => {
+                if let Err(e) = key.parse::<SigningKey>() {
+                    return Err(ConfigError::Invalid(format!("token_signing_key: {}", e)));
+                }
+            }
+            _ => {}
+        }
+        match (&self.tls.cert, &self.tls.key) {
+            (Some(_), None) | (None, Some(_)) => {
+                return invalid("tls.cert and tls.key must be set together")
+            }
+            (None, None) if self.tls.client_ca.is_some() => {
+                return invalid("tls.client_ca requires tls.cert and tls.key")
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn security_level(&self) -> Result<SecurityLevel, ConfigError> {
+        SecurityLevel::from_bits(self.min_security_level).ok_or_else(|| {
+            ConfigError::Invalid("min_security_level must be 80, 112 or 128".to_string())
+        })
+    }
+
+    pub fn listen_address(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+
+    pub fn is_enabled(&self, mode: AuthMode) -> bool {
+        self.modes.contains(&mode)
+    }
+
+    pub fn attempt_ttl(&self) -> Duration {
+        Duration::from_secs(self.attempt_ttl_secs)
+    }
+
+    pub fn session_timeouts(&self) -> SessionTimeouts {
+        SessionTimeouts {
+            idle: Duration::from_secs(self.session_idle_timeout_secs),
+            absolute: Duration::from_secs(self.session_absolute_timeout_secs),
+        }
+    }
+
+    pub fn sweep_period(&self) -> Duration {
+        Duration::from_secs(self.sweep_period_secs)
+    }
+
+    pub fn token_signing_key(&self) -> Result<Option<SigningKey>, ConfigError> {
+        self.token_signing_key
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| ConfigError::Invalid(format!("token_signing_key: {}", e)))
+    }
+
+    ///
+    /// TOML of the configuration for `--print-config`: only the key id of the token
+    /// signing key is written, its secret is redacted
+    ///
+    pub fn to_toml(&self) -> String {
+        let mut config = self.clone();
+        if let Some(key) = &mut config.token_signing_key {
+            let kid = key.split_once(':').map_or("", |(kid, _)| kid);
+            *key = format!("{}:{}", kid, REDACTED);
+        }
+        toml::to_string(&config).expect("the configuration is serializable")
+    }
+}
+
+///
+/// Serde of the values in their text form, through `Display` and `FromStr`
+///
+mod text {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+mod text_list {
+    use serde::ser::SerializeSeq;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        values: &[T],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for value in values {
+            seq.serialize_element(&value.to_string())?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|value| value.parse().map_err(de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.toml");
+        std::fs::write(
+            &path,
+            r#"
+host = "0.0.0.0"
+port = 9000
+group = "ffdhe3072"
+modes = ["exponentiation", "pedersen-opening"]
+sweep_period_secs = 10
+store = "sqlite:/var/lib/zkp/users.db"
+
+[tls]
+cert = "server.pem"
+key = "server.key"
+"#,
+        )
+        .unwrap();
+        let env = [
+            ("ZKP_SWEEP_PERIOD", "20"),
+            ("ZKP_ATTEMPT_TTL", "30"),
+            ("ZKP_MODES", "pedersen-opening"),
+            ("ZKP_MIN_SECURITY_LEVEL", "128"),
+            ("HOME", "/root"),
+        ];
+        let args = ServerArgs::try_parse_with_env(
+            [
+                "server",
+                "--config",
+                path.to_str().unwrap(),
+                "--port",
+                "9443",
+                "--attempt-ttl",
+                "45",
+                "--modes",
+                "elliptic-curve-chaum-pedersen,exponentiation",
+            ],
+            env.map(|(name, value)| (name.into(), value.into())),
+        )
+        .unwrap();
+        let config = ServerConfig::load(&args).unwrap();
+
+        assert_eq!(config.host, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(config.listen_address(), "0.0.0.0:9443".parse().unwrap());
+        assert_eq!(config.group, NamedGroup::Ffdhe3072);
+        assert_eq!(
+            config.modes,
+            [
+                AuthMode::EllipticCurveChaumPedersen,
+                AuthMode::Exponentiation
+            ]
+        );
+        assert!(!config.is_enabled(AuthMode::PedersenOpening));
+        assert_eq!(config.sweep_period(), Duration::from_secs(20));
+        assert_eq!(config.security_level().unwrap(), SecurityLevel::BITS_128);
+        assert_eq!(config.attempt_ttl(), Duration::from_secs(45));
+        assert_eq!(
+            config.store,
+            StoreBackend::Sqlite("/var/lib/zkp/users.db".into())
+        );
+        assert_eq!(config.tls.cert, Some("server.pem".into()));
+        assert_eq!(config.session_timeouts(), DEFAULT_SESSION_TIMEOUTS);
+
+        // the names of the earlier versions are read, the new ones take precedence
+        let aliases = |env: &[(&str, &str)]| {
+            let vars = env.iter().map(|(name, value)| (name.into(), value.into()));
+            ServerArgs::try_parse_with_env(["server"], vars).unwrap()
+        };
+        let args = aliases(&[
+            ("USER_STORE", "sqlite:old.db"),
+            ("SESSION_TOKENS", "signed"),
+            ("TOKEN_SIGNING_KEY", "k0:00"),
+        ]);
+        assert_eq!(args.store, Some(StoreBackend::Sqlite("old.db".into())));
+        assert_eq!(args.session_tokens, Some(SessionTokenKind::Signed));
+        assert_eq!(args.token_signing_key.as_deref(), Some("k0:00"));
+        let args = aliases(&[("ZKP_STORE", "memory"), ("USER_STORE", "sqlite:old.db")]);
+        assert_eq!(args.store, Some(StoreBackend::Memory));
+        let error =
+            ServerArgs::try_parse_with_env(["server"], [("ZKP_ATTEMPT_TTL".into(), "soon".into())])
+                .unwrap_err();
+        assert!(error.to_string().contains("--attempt-ttl"));
+
+        let error = ServerConfig::from_file(&dir.path().join("missing.toml")).unwrap_err();
+        assert!(matches!(error, ConfigError::Read(..)));
+        std::fs::write(&path, "prot = 9000").unwrap();
+        let error = ServerConfig::from_file(&path).unwrap_err();
+        assert!(matches!(error, ConfigError::Parse(..)));
+        assert!(ServerArgs::try_parse_from(["server", "--group", "modp1024"]).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ServerConfig::default().validate().is_ok());
+        let invalid = [
+            ServerConfig {
+                modes: vec![],
+                ..Default::default()
+            },
+            ServerConfig {
+                attempt_ttl_secs: 0,
+                ..Default::default()
+            },
+            ServerConfig {
+                min_security_level: 100,
+                ..Default::default()
+            },
+            ServerConfig {
+                session_idle_timeout_secs: 3600,
+                session_absolute_timeout_secs: 60,
+                ..Default::default()
+            },
+            ServerConfig {
+                token_signing_key: Some("k1:00".to_string()),
+                session_tokens: SessionTokenKind::Signed,
+                ..Default::default()
+            },
+            ServerConfig {
+                tls: TlsConfig {
+                    cert: Some("server.pem".into()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(
+                matches!(config.validate(), Err(ConfigError::Invalid(_))),
+                "{:?}",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn test_print_config() {
+        let key = SigningKey::generate("k1").unwrap();
+        let config = ServerConfig {
+            session_tokens: SessionTokenKind::Signed,
+            token_signing_key: Some(key.to_secret_string()),
+            ..Default::default()
+        };
+        let text = config.to_toml();
+        assert!(text.contains("token_signing_key = \"k1:<redacted>\""));
+        assert!(!text.contains(&key.to_secret_string()));
+        assert!(text.contains(
+            "modes = [\"exponentiation\", \"elliptic-curve-chaum-pedersen\", \"pedersen-opening\"]"
+        ));
+
+        // the printed configuration is a valid file, with the same values but the key
+        let printed: ServerConfig = toml::from_str(&text).unwrap();
+        assert_eq!(
+            printed,
+            ServerConfig {
+                token_signing_key: Some("k1:<redacted>".to_string()),
+                ..config
+            }
+        );
+        assert_eq!(
+            toml::from_str::<ServerConfig>(&ServerConfig::default().to_toml()).unwrap(),
+            ServerConfig::default()
+        );
+    }
+}
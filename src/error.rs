@@ -0,0 +1,401 @@
+// Errors of the library and their mapping onto the gRPC status codes
+// A status built from a ZkpError carries a google.rpc.ErrorInfo in its details, whose
+// reason is stable so that the clients do not have to parse the messages
+
+use crate::chaum_pedersen::ParameterError;
+use crate::group::DecodeError;
+use crate::kdf::KdfError;
+use crate::store::{AuthMode, StoreError};
+use crate::token::TokenError;
+use log::error;
+use prost::Message;
+use std::collections::HashMap;
+use std::fmt;
+use tonic::codegen::Bytes;
+use tonic::{Code, Status};
+
+/// Domain of the ErrorInfo details of the statuses
+pub const ERROR_DOMAIN: &str = "zkp-protocol-ex";
+const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+#[derive(Debug)]
+pub enum ZkpError {
+    /// The field is not the canonical encoding of a value in range
+    InvalidEncoding {
+        field: String,
+        reason: DecodeError,
+    },
+    /// The element of the field is the identity
+    IdentityElement {
+        field: String,
+    },
+    /// The element of the field is outside of the prime order subgroup
+    NotInSubgroup {
+        field: String,
+    },
+    /// Any other malformed field
+    InvalidArgument {
+        field: String,
+        reason: String,
+    },
+    UnknownUser(String),
+    UserExists(String),
+    /// The user has not registered this credential (a public pair, a commitment, a salt)
+    MissingCredential {
+        user: String,
+        credential: &'static str,
+    },
+    UnknownChallenge(String),
+    ExpiredChallenge(String),
+    ChallengeModeMismatch {
+        auth_id: String,
+        expected: AuthMode,
+        found: AuthMode,
+    },
+    VerificationFailed {
+        user: String,
+    },
+    /// The authentication mode is not enabled in the configuration of the server
+    ModeDisabled(AuthMode),
+    InvalidSession,
+    Kdf(KdfError),
+    Token(TokenError),
+    Parameters(ParameterError),
+    Store(StoreError),
+}
+
+impl ZkpError {
+    ///
+    /// Error of the field whose bytes were rejected by `PrimeOrderGroup::decode_*`
+    ///
+    pub fn decoding(field: &str, reason: DecodeError) -> Self {
+        let field = field.to_string();
+        match reason {
+            DecodeError::Identity => ZkpError::IdentityElement { field },
+            DecodeError::NotInSubgroup => ZkpError::NotInSubgroup { field },
+            reason => ZkpError::InvalidEncoding { field, reason },
+        }
+    }
+
+    pub fn invalid_argument(field: &str, reason: impl Into<String>) -> Self {
+        ZkpError::InvalidArgument {
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        match self {
+            ZkpError::InvalidEncoding { .. }
+            | ZkpError::IdentityElement { .. }
+            | ZkpError::NotInSubgroup { .. }
+            | ZkpError::InvalidArgument { .. }
+            | ZkpError::Kdf(_) => Code::InvalidArgument,
+            ZkpError::UnknownUser(_) => Code::NotFound,
+            ZkpError::UserExists(_) => Code::AlreadyExists,
+            ZkpError::MissingCredential { .. } | ZkpError::ChallengeModeMismatch { .. } => {
+                Code::FailedPrecondition
+            }
+            ZkpError::UnknownChallenge(_)
+            | ZkpError::ExpiredChallenge(_)
+            | ZkpError::InvalidSession
+            | ZkpError::Token(_) => Code::Unauthenticated,
+            ZkpError::VerificationFailed { .. } => Code::PermissionDenied,
+            ZkpError::ModeDisabled(_) => Code::Unimplemented,
+            ZkpError::Parameters(_) | ZkpError::Store(_) => Code::Internal,
+        }
+    }
+
+    ///
+    /// Reason of the ErrorInfo, UPPER_SNAKE_CASE as recommended by google.rpc
+    ///
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ZkpError::InvalidEncoding { .. } => "INVALID_ENCODING",
+            ZkpError::IdentityElement { .. } => "IDENTITY_ELEMENT",
+            ZkpError::NotInSubgroup { .. } => "NOT_IN_SUBGROUP",
+            ZkpError::InvalidArgument { .. } => "INVALID_ARGUMENT",
+            ZkpError::UnknownUser(_) => "UNKNOWN_USER",
+            ZkpError::UserExists(_) => "USER_EXISTS",
+            ZkpError::MissingCredential { .. } => "MISSING_CREDENTIAL",
+            ZkpError::UnknownChallenge(_) => "UNKNOWN_CHALLENGE",
+            ZkpError::ExpiredChallenge(_) => "EXPIRED_CHALLENGE",
+            ZkpError::ChallengeModeMismatch { .. } => "CHALLENGE_MODE_MISMATCH",
+            ZkpError::VerificationFailed { .. } => "VERIFICATION_FAILED",
+            ZkpError::ModeDisabled(_) => "MODE_DISABLED",
+            ZkpError::InvalidSession => "INVALID_SESSION",
+            ZkpError::Kdf(_) => "INVALID_KDF_PARAMETERS",
+            ZkpError::Token(_) => "INVALID_TOKEN",
+            ZkpError::Parameters(_) => "INVALID_GROUP_PARAMETERS",
+            ZkpError::Store(_) => "STORAGE_ERROR",
+        }
+    }
+
+    ///
+    /// Metadata of the ErrorInfo: the field, user or auth_id the error is about
+    ///
+    pub fn metadata(&self) -> HashMap<String, String> {
+        let entries: Vec<(&str, String)> = match self {
+            ZkpError::InvalidEncoding { field, .. }
+            | ZkpError::IdentityElement { field }
+            | ZkpError::NotInSubgroup { field }
+            | ZkpError::InvalidArgument { field, .. } => vec![("field", field.clone())],
+            ZkpError::UnknownUser(user)
+            | ZkpError::UserExists(user)
+            | ZkpError::VerificationFailed { user } => vec![("user", user.clone())],
+            ZkpError::MissingCredential { user, credential } => {
+                vec![
+                    ("user", user.clone()),
+                    ("credential", credential.to_string()),
+                ]
+            }
+            ZkpError::UnknownChallenge(auth_id) | ZkpError::ExpiredChallenge(auth_id) => {
+                vec![("auth_id", auth_id.clone())]
+            }
+            ZkpError::ChallengeModeMismatch {
+                auth_id,
+                expected,
+                found,
+            } => vec![
+                ("auth_id", auth_id.clone()),
+                ("expected_mode", expected.name().to_string()),
+                ("mode", found.name().to_string()),
+            ],
+            ZkpError::ModeDisabled(mode) => vec![("mode", mode.name().to_string())],
+            _ => vec![],
+        };
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+}
+
+impl fmt::Display for ZkpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZkpError::InvalidEncoding { field, reason } => {
+                write!(f, "Invalid {}: {}", field, reason)
+            }
+            ZkpError::IdentityElement { field } => {
+                write!(f, "Invalid {}: {}", field, DecodeError::Identity)
+            }
+            ZkpError::NotInSubgroup { field } => {
+                write!(f, "Invalid {}: {}", field, DecodeError::NotInSubgroup)
+            }
+            ZkpError::InvalidArgument { field, reason } => {
+                write!(f, "Invalid {}: {}", field, reason)
+            }
+            ZkpError::UnknownUser(user) => write!(f, "User: {} not found in database", user),
+            ZkpError::UserExists(user) => write!(
+                f,
+                "User: {} is already registered, use RotateCredential to replace its credential",
+                user
+            ),
+            ZkpError::MissingCredential { user, credential } => {
+                write!(f, "User: {} has no {} registered", user, credential)
+            }
+            ZkpError::UnknownChallenge(auth_id) => {
+                write!(f, "Authentication ID: {} not found in database", auth_id)
+            }
+            ZkpError::ExpiredChallenge(auth_id) => {
+                write!(f, "Authentication ID: {} expired", auth_id)
+            }
+            ZkpError::ChallengeModeMismatch {
+                auth_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Authentication ID: {} is a {:?} attempt, not {:?}",
+                auth_id, found, expected
+            ),
+            ZkpError::VerificationFailed { user } => {
+                write!(f, "Wrong proof of knowledge for user {}", user)
+            }
+            ZkpError::ModeDisabled(mode) => {
+                write!(f, "The {} authentication is disabled on this server", mode)
+            }
+            ZkpError::InvalidSession => write!(f, "Invalid or expired session"),
+            ZkpError::Kdf(e) => write!(f, "{}", e),
+            ZkpError::Token(e) => write!(f, "Invalid session: {}", e),
+            ZkpError::Parameters(e) => write!(f, "Invalid group parameters: {}", e),
+            ZkpError::Store(e) => write!(f, "Storage error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ZkpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ZkpError::InvalidEncoding { reason, .. } => Some(reason),
+            ZkpError::Kdf(e) => Some(e),
+            ZkpError::Token(e) => Some(e),
+            ZkpError::Parameters(e) => Some(e),
+            ZkpError::Store(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<KdfError> for ZkpError {
+    fn from(e: KdfError) -> Self {
+        ZkpError::Kdf(e)
+    }
+}
+
+impl From<TokenError> for ZkpError {
+    fn from(e: TokenError) -> Self {
+        ZkpError::Token(e)
+    }
+}
+
+impl From<ParameterError> for ZkpError {
+    fn from(e: ParameterError) -> Self {
+        ZkpError::Parameters(e)
+    }
+}
+
+impl From<StoreError> for ZkpError {
+    fn from(e: StoreError) -> Self {
+        ZkpError::Store(e)
+    }
+}
+
+///
+/// google.rpc.ErrorInfo
+/// https://github.com/googleapis/googleapis/blob/master/google/rpc/error_details.proto
+///
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorInfo {
+    #[prost(string, tag = "1")]
+    pub reason: String,
+    #[prost(string, tag = "2")]
+    pub domain: String,
+    #[prost(map = "string, string", tag = "3")]
+    pub metadata: HashMap<String, String>,
+}
+
+///
+/// google.protobuf.Any
+///
+#[derive(Clone, PartialEq, Message)]
+struct Any {
+    #[prost(string, tag = "1")]
+    type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    value: Vec<u8>,
+}
+
+///
+/// google.rpc.Status, the encoding expected in the details of a tonic Status
+///
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<Any>,
+}
+
+impl From<ZkpError> for Status {
+    ///
+    /// The internal errors are logged, their status only says that something failed
+    ///
+    fn from(e: ZkpError) -> Self {
+        let code = e.code();
+        let message = match &e {
+            ZkpError::Store(_) => {
+                error!("{}", e);
+                "Internal storage error".to_string()
+            }
+            ZkpError::Parameters(_) => {
+                error!("{}", e);
+                "Internal error".to_string()
+            }
+            _ => e.to_string(),
+        };
+        let info = ErrorInfo {
+            reason: e.reason().to_string(),
+            domain: ERROR_DOMAIN.to_string(),
+            metadata: e.metadata(),
+        };
+        let details = RpcStatus {
+            code: code as i32,
+            message: message.clone(),
+            details: vec![Any {
+                type_url: ERROR_INFO_TYPE_URL.to_string(),
+                value: info.encode_to_vec(),
+            }],
+        };
+        Status::with_details(code, message, Bytes::from(details.encode_to_vec()))
+    }
+}
+
+///
+/// ErrorInfo of the details of a status, None if the server did not send one
+///
+pub fn error_info(status: &Status) -> Option<ErrorInfo> {
+    let details = RpcStatus::decode(status.details()).ok()?;
+    details
+        .details
+        .into_iter()
+        .find(|any| any.type_url == ERROR_INFO_TYPE_URL)
+        .and_then(|any| ErrorInfo::decode(any.value.as_slice()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_details() {
+        let status = Status::from(ZkpError::decoding("y1", DecodeError::NotInSubgroup));
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "Invalid y1: the element is not in the prime order subgroup"
+        );
+        let info = error_info(&status).unwrap();
+        assert_eq!(info.reason, "NOT_IN_SUBGROUP");
+        assert_eq!(info.domain, ERROR_DOMAIN);
+        assert_eq!(info.metadata["field"], "y1");
+
+        // the internal errors do not leak their cause
+        let e = ZkpError::Store(StoreError::InvalidBackend("secret path".to_string()));
+        let status = Status::from(e);
+        assert_eq!(status.code(), Code::Internal);
+        assert!(!status.message().contains("secret path"));
+        assert_eq!(error_info(&status).unwrap().reason, "STORAGE_ERROR");
+
+        assert_eq!(error_info(&Status::not_found("user")), None);
+    }
+
+    #[test]
+    fn test_codes() {
+        let mismatch = ZkpError::ChallengeModeMismatch {
+            auth_id: "id".to_string(),
+            expected: AuthMode::Exponentiation,
+            found: AuthMode::PedersenOpening,
+        };
+        assert_eq!(mismatch.code(), Code::FailedPrecondition);
+        assert_eq!(mismatch.metadata()["mode"], "pedersen-opening");
+        assert_eq!(
+            ZkpError::ExpiredChallenge("id".to_string()).code(),
+            Code::Unauthenticated
+        );
+        assert_eq!(
+            ZkpError::VerificationFailed {
+                user: "alice".to_string()
+            }
+            .code(),
+            Code::PermissionDenied
+        );
+        assert_eq!(
+            ZkpError::decoding("t", DecodeError::Empty).reason(),
+            "INVALID_ENCODING"
+        );
+    }
+}
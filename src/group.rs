@@ -0,0 +1,456 @@
+// Prime order group abstraction, the protocols of the `sigma` module are written once
+// over this trait and run over the modp groups as well as over Ristretto.
+// `ZKPProtocol` keeps its public elements as BigUint and its scalars in the fixed-width
+// Montgomery backend of the `montgomery` module, built once with the parameters
+
+use crate::chaum_pedersen::ZKPProtocol;
+use crate::montgomery::{with_backend, ModpScalar, ScalarLimbs};
+use crate::pedersen_elliptic_curve::{generate_random_scalar, ZKPEllipticCurve};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, MultiscalarMul};
+use num_bigint::BigUint;
+use sha3::{Digest, Sha3_512};
+use std::fmt;
+
+///
+/// Group of prime order q with two generators g and h whose relative discrete log is unknown.
+/// The group law is written multiplicatively: `mul` is the group operation and `exp` the
+/// repeated operation (g^x mod p for the modp groups, x * G for Ristretto)
+///
+pub trait PrimeOrderGroup {
+    type Element: Clone + PartialEq + fmt::Debug;
+    type Scalar: Clone + PartialEq + fmt::Debug;
+
+    fn generator(&self) -> &Self::Element;
+    fn second_generator(&self) -> &Self::Element;
+    fn identity(&self) -> Self::Element;
+
+    fn exp(&self, base: &Self::Element, exponent: &Self::Scalar) -> Self::Element;
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    ///
+    /// a^x * b^y, backends with a multi-exponentiation override it
+    ///
+    fn exp2(
+        &self,
+        a: &Self::Element,
+        x: &Self::Scalar,
+        b: &Self::Element,
+        y: &Self::Scalar,
+    ) -> Self::Element {
+        self.mul(&self.exp(a, x), &self.exp(b, y))
+    }
+
+    ///
+    /// Arithmetic in Z/qZ, the results are reduced mod q
+    ///
+    fn scalar_add(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_sub(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_mul(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_neg(&self, a: &Self::Scalar) -> Self::Scalar;
+    fn random_scalar(&self) -> Self::Scalar;
+
+    ///
+    /// Number of uniformly random bytes reduced by `scalar_from_uniform_bytes`, large enough
+    /// that the reduction bias is negligible
+    ///
+    fn uniform_bytes_len(&self) -> usize;
+    ///
+    /// Scalar from `uniform_bytes_len` uniformly random bytes (KDF or hash output),
+    /// any other length is rejected
+    ///
+    fn scalar_from_uniform_bytes(&self, bytes: &[u8]) -> Result<Self::Scalar, DecodeError>;
+
+    ///
+    /// Scalar derived from the domain, the group description and the length-prefixed parts,
+    /// used for the Fiat-Shamir challenges
+    ///
+    fn hash_to_scalar(&self, domain: &[u8], parts: &[&[u8]]) -> Self::Scalar;
+
+    fn encode_element(&self, element: &Self::Element) -> Vec<u8>;
+    ///
+    /// Element of the canonical encoding `bytes`: the identity and the values outside of the
+    /// prime order subgroup are rejected, a proof over them would not bind the secret
+    ///
+    fn decode_element(&self, bytes: &[u8]) -> Result<Self::Element, DecodeError>;
+    fn encode_scalar(&self, scalar: &Self::Scalar) -> Vec<u8>;
+    ///
+    /// Scalar in [0, q[ of the canonical encoding `bytes`
+    ///
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Self::Scalar, DecodeError>;
+}
+
+///
+/// Why some bytes are not an acceptable element or scalar
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    Empty,
+    InvalidLength { expected: usize, found: usize },
+    NonCanonical,
+    OutOfRange,
+    Identity,
+    NotInSubgroup,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "the value is empty"),
+            DecodeError::InvalidLength { expected, found } => {
+                write!(f, "expected {} bytes, found {}", expected, found)
+            }
+            DecodeError::NonCanonical => write!(f, "the encoding is not canonical"),
+            DecodeError::OutOfRange => write!(f, "the value is out of range"),
+            DecodeError::Identity => write!(f, "the element is the identity"),
+            DecodeError::NotInSubgroup => {
+                write!(f, "the element is not in the prime order subgroup")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+///
+/// Unsigned big-endian integer without leading zeros, zero is encoded as a single 0 byte
+///
+fn decode_biguint(bytes: &[u8]) -> Result<BigUint, DecodeError> {
+    match bytes {
+        [] => Err(DecodeError::Empty),
+        [0, _, ..] => Err(DecodeError::NonCanonical),
+        _ => Ok(BigUint::from_bytes_be(bytes)),
+    }
+}
+
+///
+/// Element of the order q subgroup of Z/pZ*, other than 1
+///
+pub(crate) fn decode_modp_element(
+    bytes: &[u8],
+    p: &BigUint,
+    q: &BigUint,
+) -> Result<BigUint, DecodeError> {
+    let one = BigUint::from(1u32);
+    let element = decode_biguint(bytes)?;
+    if element == BigUint::from(0u32) || element >= *p {
+        return Err(DecodeError::OutOfRange);
+    }
+    if element == one {
+        return Err(DecodeError::Identity);
+    }
+    if element.modpow(q, p) != one {
+        return Err(DecodeError::NotInSubgroup);
+    }
+    Ok(element)
+}
+
+pub(crate) fn decode_modp_scalar(bytes: &[u8], q: &BigUint) -> Result<BigUint, DecodeError> {
+    let scalar = decode_biguint(bytes)?;
+    if scalar >= *q {
+        return Err(DecodeError::OutOfRange);
+    }
+    Ok(scalar)
+}
+
+impl PrimeOrderGroup for ZKPProtocol {
+    type Element = BigUint;
+    type Scalar = ModpScalar;
+
+    fn generator(&self) -> &BigUint {
+        self.g()
+    }
+
+    fn second_generator(&self) -> &BigUint {
+        self.h()
+    }
+
+    fn identity(&self) -> BigUint {
+        BigUint::from(1u32)
+    }
+
+    ///
+    /// Constant-time in the exponent, the elements are public values
+    ///
+    fn exp(&self, base: &BigUint, exponent: &ModpScalar) -> BigUint {
+        with_backend!(self.backend(), group => group.element_to_biguint(
+            &group.exp(&group.element_from_biguint(base), &group.scalar(exponent))
+        ))
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a * b) % self.p()
+    }
+
+    fn scalar_add(&self, a: &ModpScalar, b: &ModpScalar) -> ModpScalar {
+        with_backend!(self.backend(), group => group
+            .scalar_add(&group.scalar(a), &group.scalar(b))
+            .wrap())
+    }
+
+    fn scalar_sub(&self, a: &ModpScalar, b: &ModpScalar) -> ModpScalar {
+        with_backend!(self.backend(), group => group
+            .scalar_sub(&group.scalar(a), &group.scalar(b))
+            .wrap())
+    }
+
+    fn scalar_mul(&self, a: &ModpScalar, b: &ModpScalar) -> ModpScalar {
+        with_backend!(self.backend(), group => group
+            .scalar_mul(&group.scalar(a), &group.scalar(b))
+            .wrap())
+    }
+
+    fn scalar_neg(&self, a: &ModpScalar) -> ModpScalar {
+        with_backend!(self.backend(), group => group.scalar_neg(&group.scalar(a)).wrap())
+    }
+
+    fn random_scalar(&self) -> ModpScalar {
+        with_backend!(self.backend(), group => group.random_scalar().wrap())
+    }
+
+    fn uniform_bytes_len(&self) -> usize {
+        with_backend!(self.backend(), group => group.uniform_bytes_len())
+    }
+
+    fn scalar_from_uniform_bytes(&self, bytes: &[u8]) -> Result<ModpScalar, DecodeError> {
+        with_backend!(self.backend(), group => {
+            group.scalar_from_uniform_bytes(bytes).map(ScalarLimbs::wrap)
+        })
+    }
+
+    ///
+    /// SHA-256(domain || p || q || g || h || parts) mod q
+    ///
+    fn hash_to_scalar(&self, domain: &[u8], parts: &[&[u8]]) -> ModpScalar {
+        with_backend!(self.backend(), group => group.hash_to_scalar(domain, parts).wrap())
+    }
+
+    fn encode_element(&self, element: &BigUint) -> Vec<u8> {
+        element.to_bytes_be()
+    }
+
+    fn decode_element(&self, bytes: &[u8]) -> Result<BigUint, DecodeError> {
+        decode_modp_element(bytes, self.p(), self.q())
+    }
+
+    fn encode_scalar(&self, scalar: &ModpScalar) -> Vec<u8> {
+        scalar.to_biguint().to_bytes_be()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<ModpScalar, DecodeError> {
+        with_backend!(self.backend(), group => group.decode_scalar(bytes).map(|s| s.wrap()))
+    }
+}
+
+impl PrimeOrderGroup for ZKPEllipticCurve {
+    type Element = RistrettoPoint;
+    type Scalar = Scalar;
+
+    fn generator(&self) -> &RistrettoPoint {
+        &self.g
+    }
+
+    fn second_generator(&self) -> &RistrettoPoint {
+        &self.h
+    }
+
+    fn identity(&self) -> RistrettoPoint {
+        RistrettoPoint::identity()
+    }
+
+    fn exp(&self, base: &RistrettoPoint, exponent: &Scalar) -> RistrettoPoint {
+        exponent * base
+    }
+
+    fn mul(&self, a: &RistrettoPoint, b: &RistrettoPoint) -> RistrettoPoint {
+        a + b
+    }
+
+    fn exp2(
+        &self,
+        a: &RistrettoPoint,
+        x: &Scalar,
+        b: &RistrettoPoint,
+        y: &Scalar,
+    ) -> RistrettoPoint {
+        RistrettoPoint::multiscalar_mul([x, y], [a, b])
+    }
+
+    fn scalar_add(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a + b
+    }
+
+    fn scalar_sub(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a - b
+    }
+
+    fn scalar_mul(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a * b
+    }
+
+    fn scalar_neg(&self, a: &Scalar) -> Scalar {
+        -a
+    }
+
+    fn random_scalar(&self) -> Scalar {
+        generate_random_scalar()
+    }
+
+    fn uniform_bytes_len(&self) -> usize {
+        64
+    }
+
+    fn scalar_from_uniform_bytes(&self, bytes: &[u8]) -> Result<Scalar, DecodeError> {
+        let wide: [u8; 64] = bytes.try_into().map_err(|_| DecodeError::InvalidLength {
+            expected: 64,
+            found: bytes.len(),
+        })?;
+        Ok(Scalar::from_bytes_mod_order_wide(&wide))
+    }
+
+    ///
+    /// SHA3-512(domain || G || H || parts) reduced mod l
+    ///
+    fn hash_to_scalar(&self, domain: &[u8], parts: &[&[u8]]) -> Scalar {
+        let mut hasher = Sha3_512::new();
+        hasher.update(domain);
+        hasher.update(self.g.compress().as_bytes());
+        hasher.update(self.h.compress().as_bytes());
+        for part in parts {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        Scalar::from_hash(hasher)
+    }
+
+    fn encode_element(&self, element: &RistrettoPoint) -> Vec<u8> {
+        element.compress().to_bytes().to_vec()
+    }
+
+    ///
+    /// Ristretto has prime order and its decompression only accepts canonical encodings
+    ///
+    fn decode_element(&self, bytes: &[u8]) -> Result<RistrettoPoint, DecodeError> {
+        let element = CompressedRistretto::from_slice(bytes)
+            .map_err(|_| DecodeError::InvalidLength {
+                expected: 32,
+                found: bytes.len(),
+            })?
+            .decompress()
+            .ok_or(DecodeError::NonCanonical)?;
+        if element == RistrettoPoint::identity() {
+            return Err(DecodeError::Identity);
+        }
+        Ok(element)
+    }
+
+    fn encode_scalar(&self, scalar: &Scalar) -> Vec<u8> {
+        scalar.to_bytes().to_vec()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Scalar, DecodeError> {
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| DecodeError::InvalidLength {
+            expected: 32,
+            found: bytes.len(),
+        })?;
+        Option::from(Scalar::from_canonical_bytes(bytes)).ok_or(DecodeError::NonCanonical)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chaum_pedersen::get_fixed_zkp_params;
+    use crate::pedersen_elliptic_curve::pedersen_setup_base_points;
+
+    fn check_group_laws<G: PrimeOrderGroup>(group: &G) {
+        let g = group.generator();
+        let x = group.random_scalar();
+        let y = group.random_scalar();
+
+        // g^x * g^y = g^(x + y) and (g^x)^y = g^(x * y)
+        let gx = group.exp(g, &x);
+        assert_eq!(
+            group.mul(&gx, &group.exp(g, &y)),
+            group.exp(g, &group.scalar_add(&x, &y))
+        );
+        assert_eq!(group.exp(&gx, &y), group.exp(g, &group.scalar_mul(&x, &y)));
+        assert_eq!(group.mul(&gx, &group.identity()), gx);
+        assert_eq!(
+            group.exp2(g, &x, group.second_generator(), &y),
+            group.mul(&gx, &group.exp(group.second_generator(), &y))
+        );
+        assert_eq!(
+            group.mul(&gx, &group.exp(g, &group.scalar_neg(&x))),
+            group.identity()
+        );
+        assert_eq!(
+            group.scalar_sub(&x, &y),
+            group.scalar_add(&x, &group.scalar_neg(&y))
+        );
+
+        // the encodings round-trip and the identity is rejected as an element
+        assert_eq!(
+            group.decode_element(&group.encode_element(g)),
+            Ok(g.clone())
+        );
+        assert_eq!(group.decode_scalar(&group.encode_scalar(&x)), Ok(x));
+        assert_eq!(
+            group.decode_element(&group.encode_element(&group.identity())),
+            Err(DecodeError::Identity)
+        );
+    }
+
+    #[test]
+    fn test_modp_group() {
+        let group = get_fixed_zkp_params();
+        check_group_laws(&group);
+        let scalar = |value: u32| group.scalar_from_biguint(&BigUint::from(value));
+        assert_eq!(group.scalar_sub(&scalar(4), &scalar(6)), scalar(9));
+        // p = 23, q = 11: 5 has order 22
+        assert_eq!(group.decode_element(&[0]), Err(DecodeError::OutOfRange));
+        assert_eq!(group.decode_element(&[23]), Err(DecodeError::OutOfRange));
+        assert_eq!(group.decode_element(&[5]), Err(DecodeError::NotInSubgroup));
+        assert_eq!(
+            group.decode_element(&[0, 4]),
+            Err(DecodeError::NonCanonical)
+        );
+        assert_eq!(group.decode_element(&[]), Err(DecodeError::Empty));
+        assert_eq!(group.decode_scalar(&[11]), Err(DecodeError::OutOfRange));
+        assert_eq!(group.decode_scalar(&[0]), Ok(scalar(0)));
+    }
+
+    #[test]
+    fn test_ristretto_group() {
+        let group = pedersen_setup_base_points();
+        check_group_laws(&group);
+        assert_eq!(
+            group.decode_element(&[0xff; 32]),
+            Err(DecodeError::NonCanonical)
+        );
+        assert_eq!(
+            group.decode_scalar(&[0xff; 32]),
+            Err(DecodeError::NonCanonical)
+        );
+        assert_eq!(
+            group.decode_scalar(&[0; 31]),
+            Err(DecodeError::InvalidLength {
+                expected: 32,
+                found: 31
+            })
+        );
+        assert_eq!(
+            group.scalar_from_uniform_bytes(&[0; 32]),
+            Err(DecodeError::InvalidLength {
+                expected: 64,
+                found: 32
+            })
+        );
+        // the parts are length-prefixed
+        assert_ne!(
+            group.hash_to_scalar(b"domain", &[b"a", b"bc"]),
+            group.hash_to_scalar(b"domain", &[b"ab", b"c"])
+        );
+    }
+}
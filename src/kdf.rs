@@ -0,0 +1,286 @@
+// Password to secret derivation with Argon2id
+// https://www.rfc-editor.org/rfc/rfc9106.html
+
+use crate::group::{DecodeError, PrimeOrderGroup};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Size of the salts generated at registration
+pub const SALT_LEN: usize = 16;
+/// Smallest salt accepted by Argon2
+pub const MIN_SALT_LEN: usize = 8;
+/// Largest Argon2 memory cost accepted (1 GiB), the parameters come from the server
+/// and a hostile one must not make the client allocate without bound
+pub const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+/// Largest number of Argon2 passes accepted
+pub const MAX_ITERATIONS: u32 = 16;
+
+///
+/// Argon2id cost parameters, stored with the salt so that the client can derive the same secret
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    ///
+    /// Cheap parameters for the tests, not for production use
+    ///
+    pub const INSECURE_FAST: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    ///
+    /// memory_kib || iterations || parallelism, big-endian, for the registration context
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.memory_kib, self.iterations, self.parallelism]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect()
+    }
+
+    ///
+    /// Err if the costs exceed `MAX_MEMORY_KIB` or `MAX_ITERATIONS`
+    /// or if Argon2 does not accept the parameters
+    ///
+    pub fn validate(&self) -> Result<(), KdfError> {
+        for (name, value, max) in [
+            ("memory_kib", self.memory_kib, MAX_MEMORY_KIB),
+            ("iterations", self.iterations, MAX_ITERATIONS),
+        ] {
+            if value > max {
+                return Err(KdfError::CostTooHigh { name, value, max });
+            }
+        }
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map(|_| ())
+            .map_err(|e| KdfError::InvalidParams(e.to_string()))
+    }
+
+    ///
+    /// `validate` with the lower maxima of `max`, such as the ones a client accepts from
+    /// the server
+    ///
+    pub fn validate_within(&self, max: &KdfParams) -> Result<(), KdfError> {
+        for (name, value, max) in [
+            ("memory_kib", self.memory_kib, max.memory_kib),
+            ("iterations", self.iterations, max.iterations),
+            ("parallelism", self.parallelism, max.parallelism),
+        ] {
+            if value > max {
+                return Err(KdfError::CostTooHigh { name, value, max });
+            }
+        }
+        self.validate()
+    }
+}
+
+impl Default for KdfParams {
+    ///
+    /// RFC 9106 second recommended option: 64 MiB, 3 passes, 4 lanes
+    ///
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KdfError {
+    SaltTooShort {
+        len: usize,
+    },
+    InvalidParams(String),
+    /// a cost parameter is above its maximum
+    CostTooHigh {
+        name: &'static str,
+        value: u32,
+        max: u32,
+    },
+    /// the Argon2 output is not accepted by the group
+    Output(DecodeError),
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfError::SaltTooShort { len } => write!(
+                f,
+                "the salt has {} bytes, at least {} are required",
+                len, MIN_SALT_LEN
+            ),
+            KdfError::InvalidParams(reason) => write!(f, "invalid KDF parameters: {}", reason),
+            KdfError::CostTooHigh { name, value, max } => {
+                write!(f, "{} is {}, at most {} is accepted", name, value, max)
+            }
+            KdfError::Output(reason) => write!(f, "invalid KDF output: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for KdfError {}
+
+///
+/// Random salt for a new registration
+///
+pub fn generate_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+///
+/// Secret exponent of the group derived from the password: the Argon2id output has
+/// `uniform_bytes_len` bytes so that its reduction mod q is uniform. The parameters are
+/// validated first, a cost above `MAX_MEMORY_KIB` or `MAX_ITERATIONS` is never run
+///
+pub fn derive_secret<G: PrimeOrderGroup>(
+    group: &G,
+    password: &[u8],
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<G::Scalar, KdfError> {
+    params.validate()?;
+    if salt.len() < MIN_SALT_LEN {
+        return Err(KdfError::SaltTooShort { len: salt.len() });
+    }
+    let output_len = group.uniform_bytes_len();
+    let argon2_params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(output_len),
+    )
+    .map_err(|e| KdfError::InvalidParams(e.to_string()))?;
+    let mut output = vec![0u8; output_len];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params)
+        .hash_password_into(password, salt, &mut output)
+        .map_err(|e| KdfError::InvalidParams(e.to_string()))?;
+    group
+        .scalar_from_uniform_bytes(&output)
+        .map_err(KdfError::Output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::named_groups::NamedGroup;
+    use crate::pedersen_elliptic_curve::pedersen_setup_base_points;
+
+    #[test]
+    fn test_derive_secret() {
+        let group = pedersen_setup_base_points();
+        let salt = generate_salt();
+        let params = KdfParams::INSECURE_FAST;
+        let x = derive_secret(&group, b"correct horse", &salt, &params).unwrap();
+        assert_eq!(
+            derive_secret(&group, b"correct horse", &salt, &params).unwrap(),
+            x
+        );
+        assert_ne!(
+            derive_secret(&group, b"correct horse", &generate_salt(), &params).unwrap(),
+            x
+        );
+        assert_ne!(
+            derive_secret(&group, b"battery staple", &salt, &params).unwrap(),
+            x
+        );
+        let other_params = KdfParams {
+            iterations: 2,
+            ..params
+        };
+        assert_ne!(
+            derive_secret(&group, b"correct horse", &salt, &other_params).unwrap(),
+            x
+        );
+    }
+
+    #[test]
+    fn test_derive_secret_modp() {
+        let group = NamedGroup::Rfc5114Modp2048Q256.params();
+        let salt = generate_salt();
+        let x = derive_secret(&group, b"correct horse", &salt, &KdfParams::INSECURE_FAST).unwrap();
+        assert!(x.to_biguint() < *group.q());
+        assert_eq!(
+            derive_secret(&group, b"correct horse", &salt, &KdfParams::INSECURE_FAST).unwrap(),
+            x
+        );
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        let group = pedersen_setup_base_points();
+        assert_eq!(
+            derive_secret(&group, b"password", b"short", &KdfParams::INSECURE_FAST),
+            Err(KdfError::SaltTooShort { len: 5 })
+        );
+        let params = KdfParams {
+            memory_kib: 1,
+            ..KdfParams::INSECURE_FAST
+        };
+        assert!(matches!(
+            derive_secret(&group, b"password", &generate_salt(), &params),
+            Err(KdfError::InvalidParams(_))
+        ));
+        assert!(params.validate().is_err());
+        assert_eq!(KdfParams::INSECURE_FAST.validate(), Ok(()));
+        assert_eq!(KdfParams::default().validate(), Ok(()));
+
+        // the costs sent by a server are bounded before any derivation
+        let params = KdfParams {
+            memory_kib: MAX_MEMORY_KIB + 1,
+            ..KdfParams::INSECURE_FAST
+        };
+        assert!(matches!(
+            derive_secret(&group, b"password", &generate_salt(), &params),
+            Err(KdfError::CostTooHigh {
+                name: "memory_kib",
+                ..
+            })
+        ));
+        assert_eq!(
+            params.validate(),
+            Err(KdfError::CostTooHigh {
+                name: "memory_kib",
+                value: MAX_MEMORY_KIB + 1,
+                max: MAX_MEMORY_KIB,
+            })
+        );
+        let params = KdfParams {
+            iterations: u32::MAX,
+            ..KdfParams::INSECURE_FAST
+        };
+        assert!(matches!(
+            params.validate(),
+            Err(KdfError::CostTooHigh {
+                name: "iterations",
+                ..
+            })
+        ));
+        let max = KdfParams::default();
+        assert_eq!(KdfParams::INSECURE_FAST.validate_within(&max), Ok(()));
+        let params = KdfParams {
+            parallelism: 8,
+            ..max
+        };
+        assert_eq!(
+            params.validate_within(&max),
+            Err(KdfError::CostTooHigh {
+                name: "parallelism",
+                value: 8,
+                max: 4,
+            })
+        );
+    }
+}
@@ -1,2 +1,19 @@
+pub mod zkp_auth {
+    include!("./zkp_auth.rs");
+}
+
 pub mod chaum_pedersen;
+pub mod client;
+pub mod config;
+pub mod error;
+pub mod group;
+pub mod kdf;
+pub mod montgomery;
+pub mod named_groups;
 pub mod pedersen_elliptic_curve;
+pub mod primality;
+pub mod session_auth;
+pub mod sigma;
+pub mod store;
+pub mod tls;
+pub mod token;
@@ -0,0 +1,590 @@
+// Fixed-width constant-time arithmetic for the modp groups: the group elements are kept in
+// Montgomery form mod p and the scalars in Z/qZ, none of the operations branch on the values
+// https://en.wikipedia.org/wiki/Montgomery_modular_multiplication
+
+use crate::chaum_pedersen::{absorb, ParameterError};
+use crate::group::{decode_modp_element, decode_modp_scalar, DecodeError, PrimeOrderGroup};
+use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+use crypto_bigint::{NonZero, RandomMod, Uint, U1024, U2048, U256, U3072, U4096, U8192};
+use num_bigint::BigUint;
+use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+
+/// Largest p supported by the fixed-width backend
+pub const MAX_P_BITS: u64 = 8192;
+/// q up to this size uses 256-bit scalars, larger q (safe-prime groups) the width of p
+pub const SMALL_Q_BITS: u64 = 256;
+
+///
+/// Scalar of Z/qZ in Montgomery form
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Zq<const LIMBS: usize>(DynResidue<LIMBS>);
+
+impl<const LIMBS: usize> Zq<LIMBS> {
+    ///
+    /// value mod q, any value of the width is accepted
+    ///
+    pub fn new(value: &Uint<LIMBS>, q: DynResidueParams<LIMBS>) -> Self {
+        Zq(DynResidue::new(value, q))
+    }
+
+    pub fn retrieve(&self) -> Uint<LIMBS> {
+        self.0.retrieve()
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        Zq(self.0 + other.0)
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        Zq(self.0 - other.0)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Zq(self.0 * other.0)
+    }
+
+    pub fn neg(&self) -> Self {
+        Zq(-self.0)
+    }
+
+    fn params(&self) -> &DynResidueParams<LIMBS> {
+        self.0.params()
+    }
+}
+
+///
+/// Scalar of a `ZKPProtocol`: a `Zq` in the width of the scalars of its group.
+/// The secrets, the nonces and the solutions stay in this form from their derivation
+/// to their encoding
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModpScalar(ScalarWidth);
+
+// the scalars of the safe-prime groups are boxed, the 256-bit ones stay small
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScalarWidth {
+    W256(Zq<{ U256::LIMBS }>),
+    W1024(Box<Zq<{ U1024::LIMBS }>>),
+    W2048(Box<Zq<{ U2048::LIMBS }>>),
+    W3072(Box<Zq<{ U3072::LIMBS }>>),
+    W4096(Box<Zq<{ U4096::LIMBS }>>),
+    W8192(Box<Zq<{ U8192::LIMBS }>>),
+}
+
+impl ModpScalar {
+    ///
+    /// Value in [0, q[, for the encodings
+    ///
+    pub fn to_biguint(&self) -> BigUint {
+        match &self.0 {
+            ScalarWidth::W256(scalar) => to_biguint(&scalar.retrieve()),
+            ScalarWidth::W1024(scalar) => to_biguint(&scalar.retrieve()),
+            ScalarWidth::W2048(scalar) => to_biguint(&scalar.retrieve()),
+            ScalarWidth::W3072(scalar) => to_biguint(&scalar.retrieve()),
+            ScalarWidth::W4096(scalar) => to_biguint(&scalar.retrieve()),
+            ScalarWidth::W8192(scalar) => to_biguint(&scalar.retrieve()),
+        }
+    }
+}
+
+///
+/// The `Zq` widths of the `ModpScalar`
+///
+pub trait ScalarLimbs: Sized {
+    fn wrap(self) -> ModpScalar;
+    fn unwrap(scalar: &ModpScalar) -> Option<Self>;
+}
+
+macro_rules! scalar_limbs {
+    ($($variant:ident => $uint:ty),*) => {
+        $(
+            impl ScalarLimbs for Zq<{ <$uint>::LIMBS }> {
+                fn wrap(self) -> ModpScalar {
+                    ModpScalar(ScalarWidth::$variant(self.into()))
+                }
+
+                fn unwrap(scalar: &ModpScalar) -> Option<Self> {
+                    match &scalar.0 {
+                        ScalarWidth::$variant(scalar) => Some(Self::clone(scalar)),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+scalar_limbs!(
+    W256 => U256,
+    W1024 => U1024,
+    W2048 => U2048,
+    W3072 => U3072,
+    W4096 => U4096,
+    W8192 => U8192
+);
+
+///
+/// Modp group with P limbs for the elements and Q limbs for the scalars.
+/// The exponentiations take exactly the number of bits of q, whatever the exponent
+///
+#[derive(Debug, Clone)]
+pub struct ModpGroup<const P: usize, const Q: usize> {
+    p: DynResidueParams<P>,
+    q: DynResidueParams<Q>,
+    q_modulus: NonZero<Uint<Q>>,
+    q_bits: usize,
+    // 2^(64 * Q) mod q, to reduce the uniform bytes two halves at a time
+    q_shift: Zq<Q>,
+    g: DynResidue<P>,
+    h: DynResidue<P>,
+}
+
+impl<const P: usize, const Q: usize> ModpGroup<P, Q> {
+    ///
+    /// Fails if p or q are even or do not fit the widths, or if g or h are not below p
+    ///
+    pub fn new(p: &BigUint, q: &BigUint, g: &BigUint, h: &BigUint) -> Result<Self, ParameterError> {
+        if !p.bit(0) {
+            return Err(ParameterError::PNotPrime);
+        }
+        if !q.bit(0) {
+            return Err(ParameterError::QNotPrime);
+        }
+        let p_params =
+            DynResidueParams::new(&to_uint::<P>(p).ok_or(ParameterError::PTooLarge {
+                bits: p.bits(),
+                max_bits: Uint::<P>::BITS as u64,
+            })?);
+        let q_modulus = to_uint::<Q>(q).ok_or(ParameterError::QTooLarge {
+            bits: q.bits(),
+            max_bits: Uint::<Q>::BITS as u64,
+        })?;
+        let q_params = DynResidueParams::new(&q_modulus);
+        let generator = |name, value: &BigUint| {
+            if value >= p {
+                return Err(ParameterError::InvalidGenerator {
+                    name,
+                    reason: "must be in ]1, p[",
+                });
+            }
+            Ok(DynResidue::new(&truncate(value), p_params))
+        };
+        Ok(ModpGroup {
+            p: p_params,
+            q: q_params,
+            q_modulus: Option::from(NonZero::new(q_modulus)).ok_or(ParameterError::QNotPrime)?,
+            q_bits: q.bits() as usize,
+            q_shift: Zq::new(
+                &truncate(&((BigUint::from(1u32) << Uint::<Q>::BITS) % q)),
+                q_params,
+            ),
+            g: generator("g", g)?,
+            h: generator("h", h)?,
+        })
+    }
+
+    ///
+    /// value mod p
+    ///
+    pub fn element_from_biguint(&self, value: &BigUint) -> DynResidue<P> {
+        DynResidue::new(&reduce(value, self.p.modulus()), self.p)
+    }
+
+    pub fn element_to_biguint(&self, element: &DynResidue<P>) -> BigUint {
+        to_biguint(&element.retrieve())
+    }
+
+    ///
+    /// value mod q, the values wider than the scalars are first reduced with BigUint
+    ///
+    pub fn scalar_from_biguint(&self, value: &BigUint) -> Zq<Q> {
+        Zq::new(&reduce(value, self.q.modulus()), self.q)
+    }
+
+    pub fn scalar_to_biguint(&self, scalar: &Zq<Q>) -> BigUint {
+        to_biguint(&scalar.retrieve())
+    }
+}
+
+impl<const P: usize, const Q: usize> ModpGroup<P, Q>
+where
+    Zq<Q>: ScalarLimbs,
+{
+    ///
+    /// The `Zq` of a scalar of this group, the scalars of another group are reduced mod q
+    ///
+    pub fn scalar(&self, scalar: &ModpScalar) -> Zq<Q> {
+        match Zq::<Q>::unwrap(scalar) {
+            Some(zq) if *zq.params() == self.q => zq,
+            _ => self.scalar_from_biguint(&scalar.to_biguint()),
+        }
+    }
+}
+
+impl<const P: usize, const Q: usize> PrimeOrderGroup for ModpGroup<P, Q> {
+    type Element = DynResidue<P>;
+    type Scalar = Zq<Q>;
+
+    fn generator(&self) -> &DynResidue<P> {
+        &self.g
+    }
+
+    fn second_generator(&self) -> &DynResidue<P> {
+        &self.h
+    }
+
+    fn identity(&self) -> DynResidue<P> {
+        DynResidue::one(self.p)
+    }
+
+    fn exp(&self, base: &DynResidue<P>, exponent: &Zq<Q>) -> DynResidue<P> {
+        base.pow_bounded_exp(&exponent.retrieve(), self.q_bits)
+    }
+
+    fn mul(&self, a: &DynResidue<P>, b: &DynResidue<P>) -> DynResidue<P> {
+        a * b
+    }
+
+    fn scalar_add(&self, a: &Zq<Q>, b: &Zq<Q>) -> Zq<Q> {
+        a.add(b)
+    }
+
+    fn scalar_sub(&self, a: &Zq<Q>, b: &Zq<Q>) -> Zq<Q> {
+        a.sub(b)
+    }
+
+    fn scalar_mul(&self, a: &Zq<Q>, b: &Zq<Q>) -> Zq<Q> {
+        a.mul(b)
+    }
+
+    fn scalar_neg(&self, a: &Zq<Q>) -> Zq<Q> {
+        a.neg()
+    }
+
+    fn random_scalar(&self) -> Zq<Q> {
+        Zq::new(&Uint::random_mod(&mut OsRng, &self.q_modulus), self.q)
+    }
+
+    fn uniform_bytes_len(&self) -> usize {
+        2 * Uint::<Q>::BYTES
+    }
+
+    ///
+    /// hi || lo = hi * 2^(64 * Q) + lo mod q, without branching on the bytes
+    ///
+    fn scalar_from_uniform_bytes(&self, bytes: &[u8]) -> Result<Zq<Q>, DecodeError> {
+        if bytes.len() != self.uniform_bytes_len() {
+            return Err(DecodeError::InvalidLength {
+                expected: self.uniform_bytes_len(),
+                found: bytes.len(),
+            });
+        }
+        let (hi, lo) = bytes.split_at(Uint::<Q>::BYTES);
+        let hi = Zq::new(&Uint::from_be_slice(hi), self.q);
+        let lo = Zq::new(&Uint::from_be_slice(lo), self.q);
+        Ok(hi.mul(&self.q_shift).add(&lo))
+    }
+
+    ///
+    /// SHA-256(domain || p || q || g || h || parts) mod q, over the BigUint encodings
+    ///
+    fn hash_to_scalar(&self, domain: &[u8], parts: &[&[u8]]) -> Zq<Q> {
+        let mut hasher = Sha256::new();
+        hasher.update(domain);
+        for value in [
+            to_biguint(self.p.modulus()),
+            to_biguint(self.q.modulus()),
+            self.element_to_biguint(&self.g),
+            self.element_to_biguint(&self.h),
+        ] {
+            absorb(&mut hasher, &value.to_bytes_be());
+        }
+        for part in parts {
+            absorb(&mut hasher, part);
+        }
+        self.scalar_from_biguint(&BigUint::from_bytes_be(&hasher.finalize()))
+    }
+
+    fn encode_element(&self, element: &DynResidue<P>) -> Vec<u8> {
+        self.element_to_biguint(element).to_bytes_be()
+    }
+
+    ///
+    /// The subgroup check runs on public values, outside of the constant-time arithmetic
+    ///
+    fn decode_element(&self, bytes: &[u8]) -> Result<DynResidue<P>, DecodeError> {
+        let p = to_biguint(self.p.modulus());
+        let q = to_biguint(self.q.modulus());
+        let value = decode_modp_element(bytes, &p, &q)?;
+        Ok(self.element_from_biguint(&value))
+    }
+
+    fn encode_scalar(&self, scalar: &Zq<Q>) -> Vec<u8> {
+        self.scalar_to_biguint(scalar).to_bytes_be()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Zq<Q>, DecodeError> {
+        let value = decode_modp_scalar(bytes, &to_biguint(self.q.modulus()))?;
+        Ok(self.scalar_from_biguint(&value))
+    }
+}
+
+///
+/// None if the value does not fit in LIMBS words
+///
+fn to_uint<const LIMBS: usize>(value: &BigUint) -> Option<Uint<LIMBS>> {
+    if value.bits() > Uint::<LIMBS>::BITS as u64 {
+        return None;
+    }
+    Some(truncate(value))
+}
+
+///
+/// The LIMBS low words of the value
+///
+fn truncate<const LIMBS: usize>(value: &BigUint) -> Uint<LIMBS> {
+    let mut bytes = value.to_bytes_le();
+    bytes.resize(Uint::<LIMBS>::BYTES, 0);
+    Uint::from_le_slice(&bytes)
+}
+
+///
+/// value mod modulus, which fits in LIMBS words
+///
+fn reduce<const LIMBS: usize>(value: &BigUint, modulus: &Uint<LIMBS>) -> Uint<LIMBS> {
+    truncate(&(value % to_biguint(modulus)))
+}
+
+fn to_biguint<const LIMBS: usize>(value: &Uint<LIMBS>) -> BigUint {
+    let bytes: Vec<u8> = value
+        .as_words()
+        .iter()
+        .rev()
+        .flat_map(|word| word.to_be_bytes())
+        .collect();
+    BigUint::from_bytes_be(&bytes)
+}
+
+///
+/// `ModpGroup` of the smallest widths fitting the parameters of a `ZKPProtocol`,
+/// built once with the parameters and shared behind an `Arc`
+///
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum ModpBackend {
+    P1024Q256(ModpGroup<{ U1024::LIMBS }, { U256::LIMBS }>),
+    P1024(ModpGroup<{ U1024::LIMBS }, { U1024::LIMBS }>),
+    P2048Q256(ModpGroup<{ U2048::LIMBS }, { U256::LIMBS }>),
+    P2048(ModpGroup<{ U2048::LIMBS }, { U2048::LIMBS }>),
+    P3072Q256(ModpGroup<{ U3072::LIMBS }, { U256::LIMBS }>),
+    P3072(ModpGroup<{ U3072::LIMBS }, { U3072::LIMBS }>),
+    P4096Q256(ModpGroup<{ U4096::LIMBS }, { U256::LIMBS }>),
+    P4096(ModpGroup<{ U4096::LIMBS }, { U4096::LIMBS }>),
+    P8192Q256(ModpGroup<{ U8192::LIMBS }, { U256::LIMBS }>),
+    P8192(ModpGroup<{ U8192::LIMBS }, { U8192::LIMBS }>),
+}
+
+impl ModpBackend {
+    ///
+    /// Fails if p has more than `MAX_P_BITS` bits or if `ModpGroup::new` rejects the parameters
+    ///
+    pub(crate) fn new(
+        p: &BigUint,
+        q: &BigUint,
+        g: &BigUint,
+        h: &BigUint,
+    ) -> Result<Self, ParameterError> {
+        let small_q = q.bits() <= SMALL_Q_BITS;
+        macro_rules! build {
+            ($variant:ident) => {
+                ModpBackend::$variant(ModpGroup::new(p, q, g, h)?)
+            };
+        }
+        Ok(match p.bits() {
+            0..=1024 if small_q => build!(P1024Q256),
+            0..=1024 => build!(P1024),
+            1025..=2048 if small_q => build!(P2048Q256),
+            1025..=2048 => build!(P2048),
+            2049..=3072 if small_q => build!(P3072Q256),
+            2049..=3072 => build!(P3072),
+            3073..=4096 if small_q => build!(P4096Q256),
+            3073..=4096 => build!(P4096),
+            4097..=MAX_P_BITS if small_q => build!(P8192Q256),
+            4097..=MAX_P_BITS => build!(P8192),
+            bits => {
+                return Err(ParameterError::PTooLarge {
+                    bits,
+                    max_bits: MAX_P_BITS,
+                })
+            }
+        })
+    }
+}
+
+///
+/// Runs `$body` with `$group` bound to the `ModpGroup` of the backend
+///
+macro_rules! with_backend {
+    ($backend:expr, $group:ident => $body:expr) => {{
+        use $crate::montgomery::ModpBackend;
+        match $backend {
+            ModpBackend::P1024Q256($group) => $body,
+            ModpBackend::P1024($group) => $body,
+            ModpBackend::P2048Q256($group) => $body,
+            ModpBackend::P2048($group) => $body,
+            ModpBackend::P3072Q256($group) => $body,
+            ModpBackend::P3072($group) => $body,
+            ModpBackend::P4096Q256($group) => $body,
+            ModpBackend::P4096($group) => $body,
+            ModpBackend::P8192Q256($group) => $body,
+            ModpBackend::P8192($group) => $body,
+        }
+    }};
+}
+pub(crate) use with_backend;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chaum_pedersen::{generate_random_value, get_fixed_zkp_params, ZKPProtocol};
+    use crate::named_groups::NamedGroup;
+    use crate::sigma;
+    use crypto_bigint::{U2048, U256};
+
+    #[test]
+    fn test_matches_biguint_arithmetic() {
+        let params = NamedGroup::Rfc5114Modp2048Q256.params();
+        let (p, q) = (params.p(), params.q());
+        let group =
+            ModpGroup::<{ U2048::LIMBS }, { U256::LIMBS }>::new(p, q, params.g(), params.h())
+                .unwrap();
+        let x = generate_random_value(q);
+        let k = generate_random_value(q);
+        let c = generate_random_value(q);
+
+        let gx = group.exp(group.generator(), &group.scalar_from_biguint(&x));
+        assert_eq!(group.element_to_biguint(&gx), params.g().modpow(&x, p));
+
+        // s = k - c * x mod q, also when c * x > k
+        let s = sigma::chaum_pedersen::solve(
+            &group,
+            &group.scalar_from_biguint(&k),
+            &group.scalar_from_biguint(&c),
+            &group.scalar_from_biguint(&x),
+        );
+        let expected = (&k + q - (&c * &x) % q) % q;
+        assert_eq!(group.scalar_to_biguint(&s), expected);
+
+        // values wider than the scalars are reduced
+        let wide = p - 1u32;
+        assert_eq!(
+            group.scalar_to_biguint(&group.scalar_from_biguint(&wide)),
+            &wide % q
+        );
+    }
+
+    #[test]
+    fn test_proofs_interchangeable_with_backend() {
+        let params = get_fixed_zkp_params();
+        let x = params.scalar_from_biguint(&BigUint::from(3u32));
+        let (y1, y2) = params.compute_public_pair(&x);
+        let (c, s) = with_backend!(params.backend(), group => {
+            let (c, s) = sigma::chaum_pedersen::prove_non_interactive(
+                group,
+                b"domain",
+                &group.scalar(&x),
+                b"context",
+            );
+            (c.wrap(), s.wrap())
+        });
+        assert!(sigma::chaum_pedersen::verify_non_interactive(
+            &params, b"domain", &c, &s, &y1, &y2, b"context"
+        ));
+        // the scalars of another group are reduced mod its q
+        let other = NamedGroup::Rfc5114Modp2048Q256.params();
+        assert_eq!(
+            other.scalar_add(&s, &other.scalar_from_biguint(&BigUint::from(0u32))),
+            other.scalar_from_biguint(&s.to_biguint())
+        );
+    }
+
+    #[test]
+    fn test_backend_rejects_parameters() {
+        let new = |p: BigUint, q: u32| {
+            ZKPProtocol::new(p, q.into(), 4u32.into(), 9u32.into()).map(|_| ())
+        };
+        assert_eq!(new(22u32.into(), 11), Err(ParameterError::PNotPrime));
+        assert_eq!(new(23u32.into(), 10), Err(ParameterError::QNotPrime));
+        assert_eq!(
+            new(7u32.into(), 3),
+            Err(ParameterError::InvalidGenerator {
+                name: "h",
+                reason: "must be in ]1, p[",
+            })
+        );
+        assert_eq!(
+            new((BigUint::from(1u32) << MAX_P_BITS) + 1u32, 11),
+            Err(ParameterError::PTooLarge {
+                bits: MAX_P_BITS + 1,
+                max_bits: MAX_P_BITS,
+            })
+        );
+        let q = (BigUint::from(1u32) << 1024) + 1u32;
+        assert_eq!(
+            ModpGroup::<1, 1>::new(&BigUint::from(23u32), &q, &4u32.into(), &9u32.into())
+                .map(|_| ()),
+            Err(ParameterError::QTooLarge {
+                bits: 1025,
+                max_bits: 64,
+            })
+        );
+    }
+
+    #[test]
+    fn test_encoding() {
+        let params = get_fixed_zkp_params();
+        let group = ModpGroup::<1, 1>::new(params.p(), params.q(), params.g(), params.h()).unwrap();
+        let element = group.decode_element(&[18]).unwrap();
+        assert_eq!(group.encode_element(&element), vec![18]);
+        assert_eq!(group.decode_element(&[0]), Err(DecodeError::OutOfRange));
+        assert_eq!(group.decode_element(&[23]), Err(DecodeError::OutOfRange));
+        assert_eq!(group.decode_element(&[5]), Err(DecodeError::NotInSubgroup));
+        assert_eq!(group.decode_scalar(&[11]), Err(DecodeError::OutOfRange));
+        assert_eq!(
+            group
+                .decode_scalar(&[0, 10])
+                .map(|s| group.encode_scalar(&s)),
+            Err(DecodeError::NonCanonical)
+        );
+        assert_eq!(
+            group.decode_scalar(&[10]).map(|s| group.encode_scalar(&s)),
+            Ok(vec![10])
+        );
+        let large = NamedGroup::Rfc5114Modp2048Q256.params();
+        assert_eq!(
+            ModpGroup::<1, 1>::new(large.p(), large.q(), large.g(), large.h()).map(|_| ()),
+            Err(ParameterError::PTooLarge {
+                bits: 2048,
+                max_bits: 64,
+            })
+        );
+
+        // 2^64 * 1 + 12 = 16 * 1 + 12 = 6 mod 11
+        let mut bytes = vec![0u8; group.uniform_bytes_len()];
+        bytes[7] = 1;
+        bytes[15] = 12;
+        assert_eq!(
+            group.scalar_to_biguint(&group.scalar_from_uniform_bytes(&bytes).unwrap()),
+            BigUint::from(6u32)
+        );
+        assert_eq!(
+            group.scalar_from_uniform_bytes(&bytes[1..]),
+            Err(DecodeError::InvalidLength {
+                expected: 16,
+                found: 15,
+            })
+        );
+    }
+}
@@ -0,0 +1,422 @@
+// Standard groups for the exponentiation authentication
+// https://datatracker.ietf.org/doc/html/rfc5114
+// https://datatracker.ietf.org/doc/html/rfc7919
+
+use crate::chaum_pedersen::ZKPProtocol;
+use num_bigint::BigUint;
+use std::fmt;
+use std::str::FromStr;
+
+///
+/// Published groups: p, the subgroup order q and the generator g come from the RFC,
+/// the second generator h is derived from the published label "zkp-protocol-ex/h/<name>"
+/// so that nobody knows log_g(h)
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedGroup {
+    /// RFC 5114 2048-bit MODP group with 224-bit prime order subgroup
+    Rfc5114Modp2048Q224,
+    /// RFC 5114 2048-bit MODP group with 256-bit prime order subgroup
+    Rfc5114Modp2048Q256,
+    /// RFC 7919 ffdhe2048
+    Ffdhe2048,
+    /// RFC 7919 ffdhe3072
+    Ffdhe3072,
+    /// RFC 7919 ffdhe4096
+    Ffdhe4096,
+}
+
+impl NamedGroup {
+    pub const ALL: [NamedGroup; 5] = [
+        NamedGroup::Rfc5114Modp2048Q224,
+        NamedGroup::Rfc5114Modp2048Q256,
+        NamedGroup::Ffdhe2048,
+        NamedGroup::Ffdhe3072,
+        NamedGroup::Ffdhe4096,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NamedGroup::Rfc5114Modp2048Q224 => "rfc5114-2048-224",
+            NamedGroup::Rfc5114Modp2048Q256 => "rfc5114-2048-256",
+            NamedGroup::Ffdhe2048 => "ffdhe2048",
+            NamedGroup::Ffdhe3072 => "ffdhe3072",
+            NamedGroup::Ffdhe4096 => "ffdhe4096",
+        }
+    }
+
+    ///
+    /// Published label of the derivation of the second generator h
+    ///
+    pub fn h_label(&self) -> String {
+        format!("zkp-protocol-ex/h/{}", self.name())
+    }
+
+    ///
+    /// The published constants are valid (`test_named_groups_are_valid`), the construction
+    /// of the parameters does not fail
+    ///
+    pub fn params(&self) -> ZKPProtocol {
+        let (p, q, g) = match self {
+            NamedGroup::Rfc5114Modp2048Q224 => (
+                from_hex(&RFC5114_2048_224_P),
+                from_hex(&RFC5114_2048_224_Q),
+                from_hex(&RFC5114_2048_224_G),
+            ),
+            NamedGroup::Rfc5114Modp2048Q256 => (
+                from_hex(&RFC5114_2048_256_P),
+                from_hex(&RFC5114_2048_256_Q),
+                from_hex(&RFC5114_2048_256_G),
+            ),
+            NamedGroup::Ffdhe2048 => safe_prime_group(&RFC7919_FFDHE2048_P),
+            NamedGroup::Ffdhe3072 => safe_prime_group(&RFC7919_FFDHE3072_P),
+            NamedGroup::Ffdhe4096 => safe_prime_group(&RFC7919_FFDHE4096_P),
+        };
+        ZKPProtocol::with_derived_h(p, q, g, self.h_label().as_bytes())
+            .expect("the named groups have a subgroup of order q")
+    }
+}
+
+impl fmt::Display for NamedGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for NamedGroup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NamedGroup::ALL
+            .into_iter()
+            .find(|group| group.name() == s)
+            .ok_or_else(|| format!("Unknown group: {}", s))
+    }
+}
+
+///
+/// Bytes of a hex constant, decoded at compile time when it initializes a `const`: an
+/// invalid constant fails the build instead of panicking at run time
+///
+pub(crate) const fn hex_bytes<const N: usize>(hex: &str) -> [u8; N] {
+    let hex = hex.as_bytes();
+    assert!(
+        hex.len() == 2 * N,
+        "the hex constant does not have the declared length"
+    );
+    let mut bytes = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        bytes[i] = (hex_digit(hex[2 * i]) << 4) | hex_digit(hex[2 * i + 1]);
+        i += 1;
+    }
+    bytes
+}
+
+const fn hex_digit(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        b'A'..=b'F' => digit - b'A' + 10,
+        _ => panic!("invalid hex digit in a constant"),
+    }
+}
+
+fn from_hex(value: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(value)
+}
+
+///
+/// p = 2q + 1, 2 generates the subgroup of order q
+///
+fn safe_prime_group(p: &[u8]) -> (BigUint, BigUint, BigUint) {
+    let p = from_hex(p);
+    let q = (&p - 1u32) >> 1;
+    (p, q, BigUint::from(2u32))
+}
+
+// RFC 5114 section 2.2, 2048-bit MODP group with 224-bit prime order subgroup
+const RFC5114_2048_224_P: [u8; 256] = hex_bytes(concat!(
+    "AD107E1E9123A9D0D660FAA79559C51FA20D64E5683B9FD1B54B1597B61D0A75",
+    "E6FA141DF95A56DBAF9A3C407BA1DF15EB3D688A309C180E1DE6B85A1274A0A6",
+    "6D3F8152AD6AC2129037C9EDEFDA4DF8D91E8FEF55B7394B7AD5B7D0B6C12207",
+    "C9F98D11ED34DBF6C6BA0B2C8BBC27BE6A00E0A0B9C49708B3BF8A3170918836",
+    "81286130BC8985DB1602E714415D9330278273C7DE31EFDC7310F7121FD5A074",
+    "15987D9ADC0A486DCDF93ACC44328387315D75E198C641A480CD86A1B9E587E8",
+    "BE60E69CC928B2B9C52172E413042E9B23F10B0E16E79763C9B53DCF4BA80A29",
+    "E3FB73C16B8E75B97EF363E2FFA31F71CF9DE5384E71B81C0AC4DFFE0C10E64F",
+));
+const RFC5114_2048_224_Q: [u8; 28] =
+    hex_bytes("801C0D34C58D93FE997177101F80535A4738CEBCBF389A99B36371EB");
+const RFC5114_2048_224_G: [u8; 256] = hex_bytes(concat!(
+    "AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3",
+    "A6824A4E10B9A6F0DD921F01A70C4AFAAB739D7700C29F52C57DB17C620A8652",
+    "BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98A",
+    "E247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D1",
+    "19529A45D6F834566E3025E316A330EFBB77A86F0C1AB15B051AE3D428C8F8AC",
+    "B70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381",
+    "B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD",
+    "7B5A0F1C71CFFF4C19C418E1F6EC017981BC087F2A7065B384B890D3191F2BFA",
+));
+
+// RFC 5114 section 2.3, 2048-bit MODP group with 256-bit prime order subgroup
+const RFC5114_2048_256_P: [u8; 256] = hex_bytes(concat!(
+    "87A8E61DB4B6663CFFBBD19C651959998CEEF608660DD0F25D2CEED4435E3B00",
+    "E00DF8F1D61957D4FAF7DF4561B2AA3016C3D91134096FAA3BF4296D830E9A7C",
+    "209E0C6497517ABD5A8A9D306BCF67ED91F9E6725B4758C022E0B1EF4275BF7B",
+    "6C5BFC11D45F9088B941F54EB1E59BB8BC39A0BF12307F5C4FDB70C581B23F76",
+    "B63ACAE1CAA6B7902D52526735488A0EF13C6D9A51BFA4AB3AD8347796524D8E",
+    "F6A167B5A41825D967E144E5140564251CCACB83E6B486F6B3CA3F7971506026",
+    "C0B857F689962856DED4010ABD0BE621C3A3960A54E710C375F26375D7014103",
+    "A4B54330C198AF126116D2276E11715F693877FAD7EF09CADB094AE91E1A1597",
+));
+const RFC5114_2048_256_Q: [u8; 32] =
+    hex_bytes("8CF83642A709A097B447997640129DA299B1A47D1EB3750BA308B0FE64F5FBD3");
+const RFC5114_2048_256_G: [u8; 256] = hex_bytes(concat!(
+    "3FB32C9B73134D0B2E77506660EDBD484CA7B18F21EF205407F4793A1A0BA125",
+    "10DBC15077BE463FFF4FED4AAC0BB555BE3A6C1B0C6B47B1BC3773BF7E8C6F62",
+    "901228F8C28CBB18A55AE31341000A650196F931C77A57F2DDF463E5E9EC144B",
+    "777DE62AAAB8A8628AC376D282D6ED3864E67982428EBC831D14348F6F2F9193",
+    "B5045AF2767164E1DFC967C1FB3F2E55A4BD1BFFE83B9C80D052B985D182EA0A",
+    "DB2A3B7313D3FE14C8484B1E052588B9B7D2BBD2DF016199ECD06E1557CD0915",
+    "B3353BBB64E0EC377FD028370DF92B52C7891428CDC67EB6184B523D1DB246C3",
+    "2F63078490F00EF8D647D148D47954515E2327CFEF98C582664B4C0F6CC41659",
+));
+
+// RFC 7919 appendix A.1, ffdhe2048 (safe prime, generator 2)
+const RFC7919_FFDHE2048_P: [u8; 256] = hex_bytes(concat!(
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B423861285C97FFFFFFFFFFFFFFFF",
+));
+
+// RFC 7919 appendix A.2, ffdhe3072 (safe prime, generator 2)
+const RFC7919_FFDHE3072_P: [u8; 384] = hex_bytes(concat!(
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035B",
+    "BC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91C",
+    "AEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF",
+    "5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E",
+    "0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B66C62E37FFFFFFFFFFFFFFFF",
+));
+
+// RFC 7919 appendix A.3, ffdhe4096 (safe prime, generator 2)
+const RFC7919_FFDHE4096_P: [u8; 512] = hex_bytes(concat!(
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035B",
+    "BC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91C",
+    "AEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF",
+    "5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E",
+    "0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B669E1EF16E6F52C3164DF4FB",
+    "7930E9E4E58857B6AC7D5F42D69F6D187763CF1D5503400487F55BA57E31CC7A",
+    "7135C886EFB4318AED6A1E012D9E6832A907600A918130C46DC778F971AD0038",
+    "092999A333CB8B7A1A1DB93D7140003C2A4ECEA9F98D0ACC0A8291CDCEC97DCF",
+    "8EC9B55A7F88A46B4DB5A851F44182E1C68A007E5E655F6AFFFFFFFFFFFFFFFF",
+));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chaum_pedersen::SecurityLevel;
+    use crate::group::PrimeOrderGroup;
+
+    // p, q and g as published in RFC 5114 sections 2.2 and 2.3, and q = (p - 1) / 2 as published
+    // in RFC 7919 appendix A (the generator of the ffdhe groups is 2)
+    const PUBLISHED_2048_224_P: &str = concat!(
+        "AD107E1E9123A9D0D660FAA79559C51FA20D64E5683B9FD1B54B1597B61D0A75",
+        "E6FA141DF95A56DBAF9A3C407BA1DF15EB3D688A309C180E1DE6B85A1274A0A6",
+        "6D3F8152AD6AC2129037C9EDEFDA4DF8D91E8FEF55B7394B7AD5B7D0B6C12207",
+        "C9F98D11ED34DBF6C6BA0B2C8BBC27BE6A00E0A0B9C49708B3BF8A3170918836",
+        "81286130BC8985DB1602E714415D9330278273C7DE31EFDC7310F7121FD5A074",
+        "15987D9ADC0A486DCDF93ACC44328387315D75E198C641A480CD86A1B9E587E8",
+        "BE60E69CC928B2B9C52172E413042E9B23F10B0E16E79763C9B53DCF4BA80A29",
+        "E3FB73C16B8E75B97EF363E2FFA31F71CF9DE5384E71B81C0AC4DFFE0C10E64F",
+    );
+    const PUBLISHED_2048_224_Q: &str = "801C0D34C58D93FE997177101F80535A4738CEBCBF389A99B36371EB";
+    const PUBLISHED_2048_224_G: &str = concat!(
+        "AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3",
+        "A6824A4E10B9A6F0DD921F01A70C4AFAAB739D7700C29F52C57DB17C620A8652",
+        "BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98A",
+        "E247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D1",
+        "19529A45D6F834566E3025E316A330EFBB77A86F0C1AB15B051AE3D428C8F8AC",
+        "B70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381",
+        "B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD",
+        "7B5A0F1C71CFFF4C19C418E1F6EC017981BC087F2A7065B384B890D3191F2BFA",
+    );
+    const PUBLISHED_2048_256_P: &str = concat!(
+        "87A8E61DB4B6663CFFBBD19C651959998CEEF608660DD0F25D2CEED4435E3B00",
+        "E00DF8F1D61957D4FAF7DF4561B2AA3016C3D91134096FAA3BF4296D830E9A7C",
+        "209E0C6497517ABD5A8A9D306BCF67ED91F9E6725B4758C022E0B1EF4275BF7B",
+        "6C5BFC11D45F9088B941F54EB1E59BB8BC39A0BF12307F5C4FDB70C581B23F76",
+        "B63ACAE1CAA6B7902D52526735488A0EF13C6D9A51BFA4AB3AD8347796524D8E",
+        "F6A167B5A41825D967E144E5140564251CCACB83E6B486F6B3CA3F7971506026",
+        "C0B857F689962856DED4010ABD0BE621C3A3960A54E710C375F26375D7014103",
+        "A4B54330C198AF126116D2276E11715F693877FAD7EF09CADB094AE91E1A1597",
+    );
+    const PUBLISHED_2048_256_Q: &str =
+        "8CF83642A709A097B447997640129DA299B1A47D1EB3750BA308B0FE64F5FBD3";
+    const PUBLISHED_2048_256_G: &str = concat!(
+        "3FB32C9B73134D0B2E77506660EDBD484CA7B18F21EF205407F4793A1A0BA125",
+        "10DBC15077BE463FFF4FED4AAC0BB555BE3A6C1B0C6B47B1BC3773BF7E8C6F62",
+        "901228F8C28CBB18A55AE31341000A650196F931C77A57F2DDF463E5E9EC144B",
+        "777DE62AAAB8A8628AC376D282D6ED3864E67982428EBC831D14348F6F2F9193",
+        "B5045AF2767164E1DFC967C1FB3F2E55A4BD1BFFE83B9C80D052B985D182EA0A",
+        "DB2A3B7313D3FE14C8484B1E052588B9B7D2BBD2DF016199ECD06E1557CD0915",
+        "B3353BBB64E0EC377FD028370DF92B52C7891428CDC67EB6184B523D1DB246C3",
+        "2F63078490F00EF8D647D148D47954515E2327CFEF98C582664B4C0F6CC41659",
+    );
+    const PUBLISHED_FFDHE2048_Q: &str = concat!(
+        "7FFFFFFFFFFFFFFFD6FC2A2C515DA54D57EE2B10139E9E78EC5CE2C1E7169B4A",
+        "D4F09B208A3219FDE649CEE7124D9F7CBE97F1B1B1863AEC7B40D901576230BD",
+        "69EF8F6AEAFEB2B09219FA8FAF83376842B1B2AA9EF68D79DAAB89AF3FABE49A",
+        "CC278638707345BBF15344ED79F7F4390EF8AC509B56F39A98566527A41D3CBD",
+        "5E0558C159927DB0E88454A5D96471FDDCB56D5BB06BFA340EA7A151EF1CA6FA",
+        "572B76F3B1B95D8C8583D3E4770536B84F017E70E6FBF176601A0266941A17B0",
+        "C8B97F4E74C2C1FFC7278919777940C1E1FF1D8DA637D6B99DDAFE5E17611002",
+        "E2C778C1BE8B41D96379A51360D977FD4435A11C30942E4BFFFFFFFFFFFFFFFF",
+    );
+    const PUBLISHED_FFDHE3072_Q: &str = concat!(
+        "7FFFFFFFFFFFFFFFD6FC2A2C515DA54D57EE2B10139E9E78EC5CE2C1E7169B4A",
+        "D4F09B208A3219FDE649CEE7124D9F7CBE97F1B1B1863AEC7B40D901576230BD",
+        "69EF8F6AEAFEB2B09219FA8FAF83376842B1B2AA9EF68D79DAAB89AF3FABE49A",
+        "CC278638707345BBF15344ED79F7F4390EF8AC509B56F39A98566527A41D3CBD",
+        "5E0558C159927DB0E88454A5D96471FDDCB56D5BB06BFA340EA7A151EF1CA6FA",
+        "572B76F3B1B95D8C8583D3E4770536B84F017E70E6FBF176601A0266941A17B0",
+        "C8B97F4E74C2C1FFC7278919777940C1E1FF1D8DA637D6B99DDAFE5E17611002",
+        "E2C778C1BE8B41D96379A51360D977FD4435A11C308FE7EE6F1AAD9DB28C81AD",
+        "DE1A7A6F7CCE011C30DA37E4EB736483BD6C8E9348FBFBF72CC6587D60C36C8E",
+        "577F0984C289C9385A098649DE21BCA27A7EA229716BA6E9B279710F38FAA5FF",
+        "AE574155CE4EFB4F743695E2911B1D06D5E290CBCD86F56D0EDFCD216AE22427",
+        "055E6835FD29EEF79E0D90771FEACEBE12F20E95B363171BFFFFFFFFFFFFFFFF",
+    );
+    const PUBLISHED_FFDHE4096_Q: &str = concat!(
+        "7FFFFFFFFFFFFFFFD6FC2A2C515DA54D57EE2B10139E9E78EC5CE2C1E7169B4A",
+        "D4F09B208A3219FDE649CEE7124D9F7CBE97F1B1B1863AEC7B40D901576230BD",
+        "69EF8F6AEAFEB2B09219FA8FAF83376842B1B2AA9EF68D79DAAB89AF3FABE49A",
+        "CC278638707345BBF15344ED79F7F4390EF8AC509B56F39A98566527A41D3CBD",
+        "5E0558C159927DB0E88454A5D96471FDDCB56D5BB06BFA340EA7A151EF1CA6FA",
+        "572B76F3B1B95D8C8583D3E4770536B84F017E70E6FBF176601A0266941A17B0",
+        "C8B97F4E74C2C1FFC7278919777940C1E1FF1D8DA637D6B99DDAFE5E17611002",
+        "E2C778C1BE8B41D96379A51360D977FD4435A11C308FE7EE6F1AAD9DB28C81AD",
+        "DE1A7A6F7CCE011C30DA37E4EB736483BD6C8E9348FBFBF72CC6587D60C36C8E",
+        "577F0984C289C9385A098649DE21BCA27A7EA229716BA6E9B279710F38FAA5FF",
+        "AE574155CE4EFB4F743695E2911B1D06D5E290CBCD86F56D0EDFCD216AE22427",
+        "055E6835FD29EEF79E0D90771FEACEBE12F20E95B34F0F78B737A9618B26FA7D",
+        "BC9874F272C42BDB563EAFA16B4FB68C3BB1E78EAA81A00243FAADD2BF18E63D",
+        "389AE44377DA18C576B50F0096CF34195483B00548C0986236E3BC7CB8D6801C",
+        "0494CCD199E5C5BD0D0EDC9EB8A0001E15276754FCC68566054148E6E764BEE7",
+        "C764DAAD3FC45235A6DAD428FA20C170E345003F2F32AFB57FFFFFFFFFFFFFFF",
+    );
+
+    fn published(hex: &str) -> BigUint {
+        BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()
+    }
+
+    #[test]
+    fn test_named_groups_are_valid() {
+        for group in NamedGroup::ALL {
+            let params = group.params();
+            assert_eq!(
+                params.validate(&SecurityLevel::BITS_112),
+                Ok(()),
+                "{}",
+                group
+            );
+        }
+    }
+
+    #[test]
+    fn test_named_groups_published_constants() {
+        for (group, p, q, g) in [
+            (
+                NamedGroup::Rfc5114Modp2048Q224,
+                PUBLISHED_2048_224_P,
+                PUBLISHED_2048_224_Q,
+                PUBLISHED_2048_224_G,
+            ),
+            (
+                NamedGroup::Rfc5114Modp2048Q256,
+                PUBLISHED_2048_256_P,
+                PUBLISHED_2048_256_Q,
+                PUBLISHED_2048_256_G,
+            ),
+        ] {
+            let params = group.params();
+            assert_eq!(*params.p(), published(p), "{}", group);
+            assert_eq!(*params.q(), published(q), "{}", group);
+            assert_eq!(*params.g(), published(g), "{}", group);
+        }
+        for (group, q) in [
+            (NamedGroup::Ffdhe2048, PUBLISHED_FFDHE2048_Q),
+            (NamedGroup::Ffdhe3072, PUBLISHED_FFDHE3072_Q),
+            (NamedGroup::Ffdhe4096, PUBLISHED_FFDHE4096_Q),
+        ] {
+            let params = group.params();
+            let q = published(q);
+            assert_eq!(*params.p(), (&q << 1) + 1u32, "{}", group);
+            assert_eq!(*params.q(), q, "{}", group);
+            assert_eq!(*params.g(), BigUint::from(2u32), "{}", group);
+        }
+    }
+
+    #[test]
+    fn test_named_groups_authentication() {
+        for group in NamedGroup::ALL {
+            let params = group.params();
+            let x = params.scalar_from_biguint(&BigUint::from(123624374743u64));
+            let k = params.random_scalar();
+            let c = params.random_scalar();
+
+            // register, challenge and verify
+            let (y1, y2) = params.compute_public_pair(&x);
+            let (r1, r2) = params.compute_public_pair(&k);
+            let s = params.compute_solution_for_challenge(&k, &c, &x);
+            assert!(
+                params.verify_solution(&c, &s, &r1, &r2, &y1, &y2),
+                "{}",
+                group
+            );
+            let wrong = params.scalar_add(&x, &params.scalar_from_biguint(&BigUint::from(1u32)));
+            let wrong = params.compute_solution_for_challenge(&k, &c, &wrong);
+            assert!(
+                !params.verify_solution(&c, &wrong, &r1, &r2, &y1, &y2),
+                "{}",
+                group
+            );
+        }
+    }
+
+    #[test]
+    fn test_named_groups_h_derivation() {
+        for group in NamedGroup::ALL {
+            let params = group.params();
+            assert!(params.verify_h_derivation(group.h_label().as_bytes()));
+            assert_ne!(params.g(), params.h());
+        }
+    }
+
+    #[test]
+    fn test_named_group_names() {
+        for group in NamedGroup::ALL {
+            assert_eq!(group.name().parse::<NamedGroup>(), Ok(group));
+        }
+        assert!("modp1024".parse::<NamedGroup>().is_err());
+    }
+}
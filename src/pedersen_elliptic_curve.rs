@@ -1,14 +1,18 @@
 // https://findora.org/faq/crypto/pedersen-commitment-with-elliptic-curves/#:~:text=A%20Pedersen%20commitment%20is%20a,information%20at%20all%20about%20m.
 
 // use bulletproofs::PedersenGens; needs nightly build
+use crate::sigma;
 use curve25519_dalek::constants;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::MultiscalarMul;
 use rand_core::OsRng;
-use sha3::Sha3_512;
+use sha3::{Digest, Sha3_512};
+
+/// Domain separation tags for the values derived by hashing
+const BLINDING_DOMAIN: &[u8] = b"zkp-protocol-ex/pedersen/blinding/v2";
+const CHAUM_PEDERSEN_DOMAIN: &[u8] = b"zkp-protocol-ex/elliptic-curve/chaum-pedersen/v1";
 
 // https://doc-internal.dalek.rs/src/bulletproofs/generators.rs.html#28-33
 #[derive(Debug)]
@@ -20,17 +24,16 @@ pub struct ZKPEllipticCurve {
 }
 
 impl ZKPEllipticCurve {
-
     ///
     /// secret * G + blinding * H mod p
-    /// 
+    ///
     pub fn pedersen_commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
-        RistrettoPoint::multiscalar_mul(&[value, blinding], &[self.g, self.h])
+        sigma::pedersen::commit(self, &value, &blinding)
     }
 
     ///
     /// Used to compute the first commitment
-    /// 
+    ///
     pub fn commit(&mut self, x_password: Scalar) -> (RistrettoPoint, Scalar, Scalar) {
         let r = random_blinding_factor();
         let commitment = self.pedersen_commit(x_password, r);
@@ -39,7 +42,7 @@ impl ZKPEllipticCurve {
 
     ///
     /// Verify the commitment after the blinding factor and the secret are revealed
-    /// 
+    ///
     pub fn verify_commitment(
         &self,
         commitment: RistrettoPoint,
@@ -49,28 +52,153 @@ impl ZKPEllipticCurve {
         let c = self.pedersen_commit(x_password, commitment_opening);
         c == commitment
     }
+
+    ///
+    /// Okamoto proof of knowledge of the opening (m, r) of C = m * G + r * H, done on the prover side
+    /// a and b random, T = a * G + b * H is sent before the challenge
+    ///
+    pub fn commit_opening_nonces(&self) -> (RistrettoPoint, Scalar, Scalar) {
+        sigma::pedersen::opening_nonces(self)
+    }
+
+    ///
+    /// solve the challenge c send by the server without revealing m nor r
+    /// s1 = a + c * m and s2 = b + c * r
+    ///
+    pub fn compute_opening_solution(
+        &self,
+        a: &Scalar,
+        b: &Scalar,
+        c: &Scalar,
+        x_password: &Scalar,
+        blinding: &Scalar,
+    ) -> (Scalar, Scalar) {
+        sigma::pedersen::solve_opening(self, a, b, c, x_password, blinding)
+    }
+
+    ///
+    /// Verify the opening proof server side
+    /// s1 * G + s2 * H = T + c * C
+    ///
+    pub fn verify_opening(
+        &self,
+        c: &Scalar,
+        s1: &Scalar,
+        s2: &Scalar,
+        t: &RistrettoPoint,
+        commitment: &RistrettoPoint,
+    ) -> bool {
+        sigma::pedersen::verify_opening(self, c, s1, s2, t, commitment)
+    }
+
+    ///
+    /// Chaum-Pedersen (discrete log equality) over Ristretto, done on the prover side
+    /// y1 = x * G and y2 = x * H
+    /// r1 = k * G and r2 = k * H
+    ///
+    pub fn compute_public_pair(&self, x: &Scalar) -> (RistrettoPoint, RistrettoPoint) {
+        sigma::chaum_pedersen::public_pair(self, x)
+    }
+
+    ///
+    /// solve the challenge c send by the server
+    /// s = k - c * x mod l (l the order of the Ristretto group)
+    ///
+    pub fn compute_solution_for_challenge(&self, k: &Scalar, c: &Scalar, x: &Scalar) -> Scalar {
+        sigma::chaum_pedersen::solve(self, k, c, x)
+    }
+
+    ///
+    /// Verify the solution server side
+    /// r1 = s * G + c * y1 and r2 = s * H + c * y2
+    ///
+    pub fn verify_solution(
+        &self,
+        c: &Scalar,
+        s: &Scalar,
+        r1: &RistrettoPoint,
+        r2: &RistrettoPoint,
+        y1: &RistrettoPoint,
+        y2: &RistrettoPoint,
+    ) -> bool {
+        sigma::chaum_pedersen::verify(self, c, s, r1, r2, y1, y2)
+    }
+
+    ///
+    /// Non-interactive Chaum-Pedersen proof (prover side), returns (c, s)
+    /// c = SHA3-512(domain || G || H || y1 || y2 || r1 || r2 || context)
+    ///
+    pub fn prove_non_interactive(&self, x: &Scalar, context: &[u8]) -> (Scalar, Scalar) {
+        sigma::chaum_pedersen::prove_non_interactive(self, CHAUM_PEDERSEN_DOMAIN, x, context)
+    }
+
+    ///
+    /// Verify a non-interactive Chaum-Pedersen proof (verifier side)
+    ///
+    pub fn verify_non_interactive(
+        &self,
+        c: &Scalar,
+        s: &Scalar,
+        y1: &RistrettoPoint,
+        y2: &RistrettoPoint,
+        context: &[u8],
+    ) -> bool {
+        sigma::chaum_pedersen::verify_non_interactive(
+            self,
+            CHAUM_PEDERSEN_DOMAIN,
+            c,
+            s,
+            y1,
+            y2,
+            context,
+        )
+    }
 }
 
-impl Default for ZKPEllipticCurve {
-    fn default() -> Self {
+impl ZKPEllipticCurve {
+    ///
+    /// G is the Ristretto base point and H is derived from a published label,
+    /// so that nobody knows the discrete log of H in base G
+    ///
+    pub fn from_label(label: &[u8]) -> Self {
         ZKPEllipticCurve {
             g: RISTRETTO_BASEPOINT_POINT,
-            h: RistrettoPoint::hash_from_bytes::<Sha3_512>(
-                RISTRETTO_BASEPOINT_COMPRESSED.as_bytes(),
-            ),
+            h: hash_to_point(label),
         }
     }
+
+    ///
+    /// Audit: re-derive H from the published label and check it matches the setup
+    ///
+    pub fn verify_h_derivation(&self, label: &[u8]) -> bool {
+        self.g == RISTRETTO_BASEPOINT_POINT && self.h == hash_to_point(label)
+    }
+}
+
+impl Default for ZKPEllipticCurve {
+    ///
+    /// The label of H is the compressed base point, as in the bulletproofs PedersenGens
+    ///
+    fn default() -> Self {
+        ZKPEllipticCurve::from_label(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes())
+    }
+}
+
+///
+/// Nothing-up-my-sleeve point: SHA3-512 of the label mapped to the curve with the
+/// Ristretto Elligator map, its discrete log is unknown
+///
+pub fn hash_to_point(label: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::hash_from_bytes::<Sha3_512>(label)
 }
 
 pub fn pedersen_setup_base_points() -> ZKPEllipticCurve {
     ZKPEllipticCurve::default()
 }
 
-pub fn create_pedersen_g_h_points() -> (RistrettoPoint, RistrettoPoint) {
+pub fn create_pedersen_g_h_points(label: &[u8]) -> (RistrettoPoint, RistrettoPoint) {
     let g = constants::RISTRETTO_BASEPOINT_POINT;
-    let mut csprng = OsRng;
-    let a = Scalar::random(&mut csprng);
-    let h = a * g;
+    let h = hash_to_point(label);
     (g, h)
 }
 
@@ -79,6 +207,39 @@ pub fn random_blinding_factor() -> Scalar {
     Scalar::random(&mut csprng)
 }
 
+///
+/// generate random challenge in Z/lZ
+///
+pub fn generate_random_scalar() -> Scalar {
+    Scalar::random(&mut OsRng)
+}
+
+///
+/// Blinding factor of the long-term registered commitment, derived from the secret and a
+/// random salt registered with the commitment: the client only needs the secret to re-open
+/// it, and the commitment of a secret changes with the salt
+///
+pub fn derive_blinding_factor(x_password: &Scalar, salt: &[u8]) -> Scalar {
+    let mut hasher = Sha3_512::new();
+    hasher.update(BLINDING_DOMAIN);
+    hasher.update((salt.len() as u64).to_be_bytes());
+    hasher.update(salt);
+    hasher.update(x_password.as_bytes());
+    Scalar::from_hash(hasher)
+}
+
+///
+/// Bytes binding a registration proof to the user and to every field of the new registration
+///
+pub fn registration_context(user: &str, fields: &[&[u8]]) -> Vec<u8> {
+    let mut context = Vec::new();
+    for field in std::iter::once(user.as_bytes()).chain(fields.iter().copied()) {
+        context.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        context.extend_from_slice(field);
+    }
+    context
+}
+
 pub fn value_to_scalar(x_password: u64) -> Scalar {
     Scalar::from(x_password)
 }
@@ -90,12 +251,7 @@ mod tests {
     #[test]
     fn test_pedersen_elliptic() {
         let x_password = Scalar::from(124253u64);
-        let g = constants::RISTRETTO_BASEPOINT_POINT;
-        let mut csprng = OsRng;
-        let a = Scalar::random(&mut csprng);
-        let h = a * g;
-
-        // let p = pedersen_setup_base_points();
+        let (g, h) = create_pedersen_g_h_points(b"zkp-protocol-ex/test");
 
         let mut zkpelliptic = ZKPEllipticCurve { g, h };
 
@@ -104,4 +260,69 @@ mod tests {
         let verified = zkpelliptic.verify_commitment(commitment, r, x);
         assert!(verified);
     }
+
+    #[test]
+    fn test_chaum_pedersen_elliptic() {
+        let zkpelliptic = pedersen_setup_base_points();
+        let x = value_to_scalar(123624374743u64);
+        let (y1, y2) = zkpelliptic.compute_public_pair(&x);
+
+        let k = generate_random_scalar();
+        let (r1, r2) = zkpelliptic.compute_public_pair(&k);
+        let c = generate_random_scalar();
+        let s = zkpelliptic.compute_solution_for_challenge(&k, &c, &x);
+        assert!(zkpelliptic.verify_solution(&c, &s, &r1, &r2, &y1, &y2));
+
+        // a wrong secret does not solve the challenge
+        let wrong = zkpelliptic.compute_solution_for_challenge(&k, &c, &value_to_scalar(1u64));
+        assert!(!zkpelliptic.verify_solution(&c, &wrong, &r1, &r2, &y1, &y2));
+
+        let (c, s) = zkpelliptic.prove_non_interactive(&x, b"alice");
+        assert!(zkpelliptic.verify_non_interactive(&c, &s, &y1, &y2, b"alice"));
+        assert!(!zkpelliptic.verify_non_interactive(&c, &s, &y1, &y2, b"bob"));
+        assert!(!zkpelliptic.verify_non_interactive(&c, &s, &y2, &y1, b"alice"));
+    }
+
+    #[test]
+    fn test_pedersen_opening_proof() {
+        let mut zkpelliptic = pedersen_setup_base_points();
+        let (commitment, r, m) = zkpelliptic.commit(value_to_scalar(124253u64));
+
+        let (t, a, b) = zkpelliptic.commit_opening_nonces();
+        let c = generate_random_scalar();
+        let (s1, s2) = zkpelliptic.compute_opening_solution(&a, &b, &c, &m, &r);
+        assert!(zkpelliptic.verify_opening(&c, &s1, &s2, &t, &commitment));
+
+        // knowing the secret without the blinding factor is not enough
+        let (s1, s2) =
+            zkpelliptic.compute_opening_solution(&a, &b, &c, &m, &random_blinding_factor());
+        assert!(!zkpelliptic.verify_opening(&c, &s1, &s2, &t, &commitment));
+    }
+
+    #[test]
+    fn test_derive_blinding_factor() {
+        let m = value_to_scalar(124253u64);
+        let r = derive_blinding_factor(&m, &[1; 16]);
+        assert_eq!(r, derive_blinding_factor(&m, &[1; 16]));
+        assert_ne!(r, derive_blinding_factor(&m, &[2; 16]));
+        assert_ne!(r, derive_blinding_factor(&value_to_scalar(1u64), &[1; 16]));
+    }
+
+    #[test]
+    fn test_h_derivation() {
+        let zkpelliptic = pedersen_setup_base_points();
+        assert!(zkpelliptic.verify_h_derivation(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes()));
+        assert!(!zkpelliptic.verify_h_derivation(b"another label"));
+
+        let (g, h) = create_pedersen_g_h_points(b"label");
+        assert!(ZKPEllipticCurve { g, h }.verify_h_derivation(b"label"));
+        assert_ne!(g, h);
+
+        // H = a * G with a known a is rejected by the audit
+        let weak = ZKPEllipticCurve {
+            g,
+            h: value_to_scalar(42u64) * g,
+        };
+        assert!(!weak.verify_h_derivation(b"label"));
+    }
 }
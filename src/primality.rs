@@ -0,0 +1,89 @@
+// Probabilistic primality testing for the group parameters
+// https://en.wikipedia.org/wiki/Miller%E2%80%93Rabin_primality_test
+
+use num_bigint::{BigUint, RandBigInt};
+
+/// Number of Miller-Rabin rounds, the error probability is below 4^-rounds
+pub const MILLER_RABIN_ROUNDS: usize = 40;
+
+const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+///
+/// Miller-Rabin test with random bases, preceded by a trial division by the small primes
+///
+pub fn is_probable_prime(n: &BigUint, rounds: usize) -> bool {
+    let mut rng = rand::thread_rng();
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+    for p in SMALL_PRIMES {
+        let p = BigUint::from(p);
+        if *n == p {
+            return true;
+        }
+        if (n % &p) == BigUint::from(0u32) {
+            return false;
+        }
+    }
+    let bases: Vec<BigUint> = (0..rounds)
+        .map(|_| rng.gen_biguint_range(&two, &(n - 1u32)))
+        .collect();
+    miller_rabin(n, &bases)
+}
+
+///
+/// n - 1 = d * 2^s, n is a probable prime if for every base a
+/// a^d = 1 mod n or a^(d * 2^r) = -1 mod n for some 0 <= r < s
+///
+pub fn miller_rabin(n: &BigUint, bases: &[BigUint]) -> bool {
+    let one = BigUint::from(1u32);
+    let n_minus_one = n - &one;
+    let s = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> s;
+    'bases: for a in bases {
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&BigUint::from(2u32), n);
+            if x == n_minus_one {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_values() {
+        let primes: Vec<u32> = (0..200u32)
+            .filter(|n| is_probable_prime(&BigUint::from(*n), MILLER_RABIN_ROUNDS))
+            .collect();
+        let expected: Vec<u32> = (2..200u32)
+            .filter(|n| (2..*n).all(|d| n % d != 0))
+            .collect();
+        assert_eq!(primes, expected);
+    }
+
+    #[test]
+    fn test_carmichael_and_mersenne() {
+        // 561 = 3 * 11 * 17 and 2^67 - 1 = 193707721 * 761838257287 are composite
+        assert!(!is_probable_prime(
+            &BigUint::from(561u32),
+            MILLER_RABIN_ROUNDS
+        ));
+        let m67 = (BigUint::from(1u32) << 67u32) - 1u32;
+        assert!(!is_probable_prime(&m67, MILLER_RABIN_ROUNDS));
+        let m127 = (BigUint::from(1u32) << 127u32) - 1u32;
+        assert!(is_probable_prime(&m127, MILLER_RABIN_ROUNDS));
+    }
+}
@@ -0,0 +1,367 @@
+// Session enforcement for the gRPC services downstream of the Auth server
+// The session_id is sent as `authorization: Bearer <session_id>`
+
+use crate::tls::{http_peer_certificate, peer_certificate, PeerCertificate};
+use crate::token::TokenVerifier;
+use crate::zkp_auth::{auth_client::AuthClient, ValidateSessionRequest, ValidateSessionResponse};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use tonic::body::BoxBody;
+use tonic::service::Interceptor;
+use tonic::transport::Channel;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Metadata key carrying the session_id
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+/// Prefix of the session_id in the authorization value
+pub const BEARER_PREFIX: &str = "Bearer ";
+
+///
+/// Caller of a request whose session was validated, in the request extensions
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser {
+    pub user: String,
+    pub expires_at: SystemTime,
+    /// Scopes of a signed token, empty for a session validated by the Auth server
+    pub scopes: Vec<String>,
+    /// Client certificate subject of the login, only known by the Auth server
+    pub client_subject: Option<String>,
+    /// Fingerprint of the client certificate the session is bound to
+    /// (`tls::certificate_fingerprint`), from the Auth server or the `cnf` claim of a token
+    pub client_fingerprint: Option<String>,
+}
+
+impl From<ValidateSessionResponse> for AuthenticatedUser {
+    fn from(response: ValidateSessionResponse) -> Self {
+        AuthenticatedUser {
+            user: response.user,
+            expires_at: SystemTime::UNIX_EPOCH
+                + Duration::from_secs(response.expires_at.max(0) as u64),
+            scopes: Vec::new(),
+            client_subject: Some(response.client_subject).filter(|subject| !subject.is_empty()),
+            client_fingerprint: Some(response.client_fingerprint)
+                .filter(|fingerprint| !fingerprint.is_empty()),
+        }
+    }
+}
+
+///
+/// Where the sessions are checked: locally for the signed tokens, with the public keys of
+/// the Auth server, or by the `ValidateSession` rpc of the Auth server for any session
+///
+#[derive(Clone)]
+pub enum SessionValidator {
+    Local(Arc<TokenVerifier>),
+    Remote(AuthClient<Channel>),
+}
+
+impl SessionValidator {
+    ///
+    /// User of the session for the request of `client`: a session bound to a client
+    /// certificate is rejected without that certificate
+    ///
+    pub async fn validate(
+        &self,
+        session_id: &str,
+        client: Option<&PeerCertificate>,
+    ) -> Result<AuthenticatedUser, Status> {
+        let user = match self {
+            SessionValidator::Local(verifier) => validate_locally(verifier, session_id)?,
+            SessionValidator::Remote(client_of_server) => {
+                let response = client_of_server
+                    .clone()
+                    .validate_session(ValidateSessionRequest {
+                        session_id: session_id.to_string(),
+                        client_fingerprint: client
+                            .map(|client| client.fingerprint.clone())
+                            .unwrap_or_default(),
+                    })
+                    .await
+                    .map_err(|status| match status.code() {
+                        tonic::Code::Unauthenticated => status,
+                        _ => Status::unavailable(format!(
+                            "Could not validate the session: {}",
+                            status.message()
+                        )),
+                    })?
+                    .into_inner();
+                response.into()
+            }
+        };
+        check_client(&user, client)?;
+        Ok(user)
+    }
+}
+
+///
+/// A session bound to a client certificate (the `cnf` claim of a token) is only accepted
+/// from the client presenting it
+///
+#[allow(clippy::result_large_err)]
+fn check_client(user: &AuthenticatedUser, client: Option<&PeerCertificate>) -> Result<(), Status> {
+    match &user.client_fingerprint {
+        Some(fingerprint) if client.map(|client| &client.fingerprint) != Some(fingerprint) => Err(
+            Status::unauthenticated("Invalid session: bound to another client certificate"),
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn validate_locally(verifier: &TokenVerifier, token: &str) -> Result<AuthenticatedUser, Status> {
+    let claims = verifier
+        .verify(token, SystemTime::now())
+        .map_err(|e| Status::unauthenticated(format!("Invalid session: {}", e)))?;
+    Ok(AuthenticatedUser {
+        expires_at: claims.expires_at(),
+        user: claims.sub,
+        scopes: claims.scopes,
+        client_subject: None,
+        client_fingerprint: claims.cnf.map(|cnf| cnf.x5t_s256),
+    })
+}
+
+///
+/// session_id of an `authorization: Bearer <session_id>` value
+///
+#[allow(clippy::result_large_err)]
+fn bearer_session_id(value: Option<&str>) -> Result<&str, Status> {
+    value
+        .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+        .filter(|session_id| !session_id.is_empty())
+        .ok_or_else(|| Status::unauthenticated("Missing session, expected a Bearer session_id"))
+}
+
+///
+/// tonic interceptor for the signed tokens: interceptors are synchronous, so a session
+/// can only be checked locally. Use `SessionAuthLayer` to validate with the Auth server.
+/// A token with a `cnf` claim is only accepted with the client certificate it names
+///
+#[derive(Clone)]
+pub struct SessionInterceptor {
+    verifier: Arc<TokenVerifier>,
+}
+
+impl SessionInterceptor {
+    pub fn new(verifier: TokenVerifier) -> Self {
+        SessionInterceptor {
+            verifier: Arc::new(verifier),
+        }
+    }
+}
+
+impl Interceptor for SessionInterceptor {
+    fn call(&mut self, mut request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        let value = request.metadata().get(AUTHORIZATION_HEADER);
+        let session_id = bearer_session_id(value.and_then(|value| value.to_str().ok()))?;
+        let user = validate_locally(&self.verifier, session_id)?;
+        check_client(&user, peer_certificate(&request).as_ref())?;
+        request.extensions_mut().insert(user);
+        Ok(request)
+    }
+}
+
+///
+/// tower layer rejecting with `Unauthenticated` the requests without a valid session, or
+/// without the client certificate their session is bound to, the `AuthenticatedUser` is
+/// added to the extensions of the others:
+/// `Server::builder().layer(SessionAuthLayer::remote(client)).add_service(...)`
+///
+#[derive(Clone)]
+pub struct SessionAuthLayer {
+    validator: SessionValidator,
+}
+
+impl SessionAuthLayer {
+    pub fn new(validator: SessionValidator) -> Self {
+        SessionAuthLayer { validator }
+    }
+
+    pub fn local(verifier: TokenVerifier) -> Self {
+        SessionAuthLayer::new(SessionValidator::Local(Arc::new(verifier)))
+    }
+
+    pub fn remote(client: AuthClient<Channel>) -> Self {
+        SessionAuthLayer::new(SessionValidator::Remote(client))
+    }
+}
+
+impl<S> Layer<S> for SessionAuthLayer {
+    type Service = SessionAuth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionAuth {
+            inner,
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionAuth<S> {
+    inner: S,
+    validator: SessionValidator,
+}
+
+impl<S, B> Service<http::Request<B>> for SessionAuth<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // the ready service is used for this request and a clone is kept for the next one
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let validator = self.validator.clone();
+        Box::pin(async move {
+            let value = request.headers().get(AUTHORIZATION_HEADER);
+            let session_id =
+                bearer_session_id(value.and_then(|value| value.to_str().ok())).map(str::to_string);
+            let client = http_peer_certificate(&request);
+            let user = match session_id {
+                Ok(session_id) => validator.validate(&session_id, client.as_ref()).await,
+                Err(status) => Err(status),
+            };
+            match user {
+                Ok(user) => {
+                    request.extensions_mut().insert(user);
+                    inner.call(request).await
+                }
+                Err(status) => Ok(status.to_http()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::{Confirmation, SigningKey, TokenClaims, TokenIssuer};
+    use std::convert::Infallible;
+
+    fn token(issuer: &TokenIssuer, exp: SystemTime) -> String {
+        bound_token(issuer, exp, None)
+    }
+
+    fn bound_token(issuer: &TokenIssuer, exp: SystemTime, fingerprint: Option<&str>) -> String {
+        issuer.issue(&TokenClaims {
+            sub: "alice".to_string(),
+            method: "exponentiation".to_string(),
+            iat: 0,
+            exp: exp
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            scopes: vec!["read".to_string()],
+            jti: "id".to_string(),
+            cnf: fingerprint.map(|fingerprint| Confirmation {
+                x5t_s256: fingerprint.to_string(),
+            }),
+        })
+    }
+
+    fn grpc_status(response: &http::Response<BoxBody>) -> Option<&str> {
+        response
+            .headers()
+            .get("grpc-status")
+            .map(|value| value.to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_layer() {
+        let issuer = TokenIssuer::new(SigningKey::generate("k1").unwrap());
+        let layer = SessionAuthLayer::local(TokenVerifier::new([issuer.verifying_key()]));
+        // the inner service answers with the user found in the extensions
+        let mut service = layer.layer(tower::service_fn(|request: http::Request<()>| async move {
+            let user = request.extensions().get::<AuthenticatedUser>().unwrap();
+            let response = http::Response::builder()
+                .header("user", user.user.as_str())
+                .body(tonic::body::empty_body())
+                .unwrap();
+            Ok::<_, Infallible>(response)
+        }));
+
+        let valid = token(&issuer, SystemTime::now() + Duration::from_secs(60));
+        let request = http::Request::builder()
+            .header(AUTHORIZATION_HEADER, format!("Bearer {}", valid))
+            .body(())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(response.headers()["user"], "alice");
+
+        // a token bound to a client certificate is rejected without it
+        let expired = token(&issuer, SystemTime::now() - Duration::from_secs(1));
+        let bound = bound_token(
+            &issuer,
+            SystemTime::now() + Duration::from_secs(60),
+            Some("fingerprint"),
+        );
+        for authorization in [
+            None,
+            Some(valid),
+            Some(format!("Bearer {}", expired)),
+            Some(format!("Bearer {}", bound)),
+        ] {
+            let mut request = http::Request::builder();
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION_HEADER, authorization);
+            }
+            let response = service.call(request.body(()).unwrap()).await.unwrap();
+            assert_eq!(
+                grpc_status(&response),
+                Some((tonic::Code::Unauthenticated as i32).to_string().as_str())
+            );
+            assert!(response.headers().get("user").is_none());
+        }
+    }
+
+    #[test]
+    fn test_interceptor() {
+        let issuer = TokenIssuer::new(SigningKey::generate("k1").unwrap());
+        let mut interceptor = SessionInterceptor::new(TokenVerifier::new([issuer.verifying_key()]));
+        let mut request = tonic::Request::new(());
+        let valid = token(&issuer, SystemTime::now() + Duration::from_secs(60));
+        request.metadata_mut().insert(
+            AUTHORIZATION_HEADER,
+            format!("Bearer {}", valid).parse().unwrap(),
+        );
+        let request = interceptor.call(request).unwrap();
+        let user = request.extensions().get::<AuthenticatedUser>().unwrap();
+        assert_eq!(user.user, "alice");
+        assert_eq!(user.scopes, ["read"]);
+
+        let status = interceptor.call(tonic::Request::new(())).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        let mut request = tonic::Request::new(());
+        let bound = bound_token(
+            &issuer,
+            SystemTime::now() + Duration::from_secs(60),
+            Some("fingerprint"),
+        );
+        request.metadata_mut().insert(
+            AUTHORIZATION_HEADER,
+            format!("Bearer {}", bound).parse().unwrap(),
+        );
+        let status = interceptor.call(request).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert_eq!(
+            status.message(),
+            "Invalid session: bound to another client certificate"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+// Sigma protocols written once over `PrimeOrderGroup`
+// https://crypto.stanford.edu/cs355/19sp/lec5.pdf
+
+///
+/// Chaum-Pedersen proof of discrete log equality: y1 = g^x and y2 = h^x
+///
+pub mod chaum_pedersen {
+    use crate::group::PrimeOrderGroup;
+
+    ///
+    /// y1 = g^x and y2 = h^x (prover side), also gives r1 = g^k and r2 = h^k
+    ///
+    pub fn public_pair<G: PrimeOrderGroup>(group: &G, x: &G::Scalar) -> (G::Element, G::Element) {
+        (
+            group.exp(group.generator(), x),
+            group.exp(group.second_generator(), x),
+        )
+    }
+
+    ///
+    /// s = k - c * x mod q
+    ///
+    pub fn solve<G: PrimeOrderGroup>(
+        group: &G,
+        k: &G::Scalar,
+        c: &G::Scalar,
+        x: &G::Scalar,
+    ) -> G::Scalar {
+        group.scalar_sub(k, &group.scalar_mul(c, x))
+    }
+
+    ///
+    /// r1 = g^s * y1^c and r2 = h^s * y2^c (verifier side)
+    ///
+    pub fn verify<G: PrimeOrderGroup>(
+        group: &G,
+        c: &G::Scalar,
+        s: &G::Scalar,
+        r1: &G::Element,
+        r2: &G::Element,
+        y1: &G::Element,
+        y2: &G::Element,
+    ) -> bool {
+        *r1 == group.exp2(group.generator(), s, y1, c)
+            && *r2 == group.exp2(group.second_generator(), s, y2, c)
+    }
+
+    ///
+    /// Fiat-Shamir: c = H(domain, group, y1, y2, r1, r2, context), returns (c, s)
+    ///
+    pub fn prove_non_interactive<G: PrimeOrderGroup>(
+        group: &G,
+        domain: &[u8],
+        x: &G::Scalar,
+        context: &[u8],
+    ) -> (G::Scalar, G::Scalar) {
+        let (y1, y2) = public_pair(group, x);
+        let k = group.random_scalar();
+        let (r1, r2) = public_pair(group, &k);
+        let c = challenge(group, domain, [&y1, &y2, &r1, &r2], context);
+        let s = solve(group, &k, &c, x);
+        (c, s)
+    }
+
+    ///
+    /// r1 and r2 are recomputed from (c, s) and must hash back to c
+    ///
+    pub fn verify_non_interactive<G: PrimeOrderGroup>(
+        group: &G,
+        domain: &[u8],
+        c: &G::Scalar,
+        s: &G::Scalar,
+        y1: &G::Element,
+        y2: &G::Element,
+        context: &[u8],
+    ) -> bool {
+        let r1 = group.exp2(group.generator(), s, y1, c);
+        let r2 = group.exp2(group.second_generator(), s, y2, c);
+        *c == challenge(group, domain, [y1, y2, &r1, &r2], context)
+    }
+
+    fn challenge<G: PrimeOrderGroup>(
+        group: &G,
+        domain: &[u8],
+        elements: [&G::Element; 4],
+        context: &[u8],
+    ) -> G::Scalar {
+        let encoded = elements.map(|e| group.encode_element(e));
+        let [y1, y2, r1, r2] = &encoded;
+        group.hash_to_scalar(domain, &[y1, y2, r1, r2, context])
+    }
+}
+
+///
+/// Schnorr proof of knowledge of x such that y = g^x, and the derived signature
+///
+pub mod schnorr {
+    use crate::group::PrimeOrderGroup;
+
+    ///
+    /// y = g^x, also gives the commitment r = g^k
+    ///
+    pub fn public_key<G: PrimeOrderGroup>(group: &G, x: &G::Scalar) -> G::Element {
+        group.exp(group.generator(), x)
+    }
+
+    ///
+    /// s = k - c * x mod q
+    ///
+    pub fn solve<G: PrimeOrderGroup>(
+        group: &G,
+        k: &G::Scalar,
+        c: &G::Scalar,
+        x: &G::Scalar,
+    ) -> G::Scalar {
+        group.scalar_sub(k, &group.scalar_mul(c, x))
+    }
+
+    ///
+    /// r = g^s * y^c
+    ///
+    pub fn verify<G: PrimeOrderGroup>(
+        group: &G,
+        c: &G::Scalar,
+        s: &G::Scalar,
+        r: &G::Element,
+        y: &G::Element,
+    ) -> bool {
+        *r == group.exp2(group.generator(), s, y, c)
+    }
+
+    ///
+    /// Non-interactive proof bound to a message: c = H(domain, group, y, r, message), returns (c, s)
+    ///
+    pub fn sign<G: PrimeOrderGroup>(
+        group: &G,
+        domain: &[u8],
+        x: &G::Scalar,
+        message: &[u8],
+    ) -> (G::Scalar, G::Scalar) {
+        let y = public_key(group, x);
+        let k = group.random_scalar();
+        let r = public_key(group, &k);
+        let c = challenge(group, domain, &y, &r, message);
+        let s = solve(group, &k, &c, x);
+        (c, s)
+    }
+
+    pub fn verify_signature<G: PrimeOrderGroup>(
+        group: &G,
+        domain: &[u8],
+        c: &G::Scalar,
+        s: &G::Scalar,
+        y: &G::Element,
+        message: &[u8],
+    ) -> bool {
+        let r = group.exp2(group.generator(), s, y, c);
+        *c == challenge(group, domain, y, &r, message)
+    }
+
+    fn challenge<G: PrimeOrderGroup>(
+        group: &G,
+        domain: &[u8],
+        y: &G::Element,
+        r: &G::Element,
+        message: &[u8],
+    ) -> G::Scalar {
+        group.hash_to_scalar(
+            domain,
+            &[&group.encode_element(y), &group.encode_element(r), message],
+        )
+    }
+}
+
+///
+/// Pedersen commitment C = g^m * h^r and Okamoto proof of knowledge of its opening (m, r)
+///
+pub mod pedersen {
+    use crate::group::PrimeOrderGroup;
+
+    pub fn commit<G: PrimeOrderGroup>(group: &G, m: &G::Scalar, r: &G::Scalar) -> G::Element {
+        group.exp2(group.generator(), m, group.second_generator(), r)
+    }
+
+    ///
+    /// a and b random, T = g^a * h^b is sent before the challenge
+    ///
+    pub fn opening_nonces<G: PrimeOrderGroup>(group: &G) -> (G::Element, G::Scalar, G::Scalar) {
+        let a = group.random_scalar();
+        let b = group.random_scalar();
+        (commit(group, &a, &b), a, b)
+    }
+
+    ///
+    /// s1 = a + c * m and s2 = b + c * r mod q
+    ///
+    pub fn solve_opening<G: PrimeOrderGroup>(
+        group: &G,
+        a: &G::Scalar,
+        b: &G::Scalar,
+        c: &G::Scalar,
+        m: &G::Scalar,
+        r: &G::Scalar,
+    ) -> (G::Scalar, G::Scalar) {
+        (
+            group.scalar_add(a, &group.scalar_mul(c, m)),
+            group.scalar_add(b, &group.scalar_mul(c, r)),
+        )
+    }
+
+    ///
+    /// g^s1 * h^s2 = T * C^c
+    ///
+    pub fn verify_opening<G: PrimeOrderGroup>(
+        group: &G,
+        c: &G::Scalar,
+        s1: &G::Scalar,
+        s2: &G::Scalar,
+        t: &G::Element,
+        commitment: &G::Element,
+    ) -> bool {
+        commit(group, s1, s2) == group.mul(t, &group.exp(commitment, c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chaum_pedersen::generate_1024bit_group_with_160bit_constants;
+    use crate::group::PrimeOrderGroup;
+    use crate::pedersen_elliptic_curve::pedersen_setup_base_points;
+
+    const DOMAIN: &[u8] = b"zkp-protocol-ex/sigma/test";
+
+    fn check_chaum_pedersen<G: PrimeOrderGroup>(group: &G) {
+        let x = group.random_scalar();
+        let (y1, y2) = chaum_pedersen::public_pair(group, &x);
+        let k = group.random_scalar();
+        let (r1, r2) = chaum_pedersen::public_pair(group, &k);
+        let c = group.random_scalar();
+        let s = chaum_pedersen::solve(group, &k, &c, &x);
+        assert!(chaum_pedersen::verify(group, &c, &s, &r1, &r2, &y1, &y2));
+        assert!(!chaum_pedersen::verify(group, &c, &s, &r2, &r1, &y1, &y2));
+
+        let (c, s) = chaum_pedersen::prove_non_interactive(group, DOMAIN, &x, b"alice");
+        assert!(chaum_pedersen::verify_non_interactive(
+            group, DOMAIN, &c, &s, &y1, &y2, b"alice"
+        ));
+        assert!(!chaum_pedersen::verify_non_interactive(
+            group, DOMAIN, &c, &s, &y1, &y2, b"bob"
+        ));
+    }
+
+    fn check_schnorr<G: PrimeOrderGroup>(group: &G) {
+        let x = group.random_scalar();
+        let y = schnorr::public_key(group, &x);
+        let k = group.random_scalar();
+        let r = schnorr::public_key(group, &k);
+        let c = group.random_scalar();
+        let s = schnorr::solve(group, &k, &c, &x);
+        assert!(schnorr::verify(group, &c, &s, &r, &y));
+        assert!(!schnorr::verify(group, &c, &s, &y, &r));
+
+        let (c, s) = schnorr::sign(group, DOMAIN, &x, b"message");
+        assert!(schnorr::verify_signature(
+            group, DOMAIN, &c, &s, &y, b"message"
+        ));
+        assert!(!schnorr::verify_signature(
+            group,
+            DOMAIN,
+            &c,
+            &s,
+            &y,
+            b"other message"
+        ));
+        assert!(!schnorr::verify_signature(
+            group,
+            b"other domain",
+            &c,
+            &s,
+            &y,
+            b"message"
+        ));
+    }
+
+    fn check_pedersen<G: PrimeOrderGroup>(group: &G) {
+        let m = group.random_scalar();
+        let r = group.random_scalar();
+        let commitment = pedersen::commit(group, &m, &r);
+
+        let (t, a, b) = pedersen::opening_nonces(group);
+        let c = group.random_scalar();
+        let (s1, s2) = pedersen::solve_opening(group, &a, &b, &c, &m, &r);
+        assert!(pedersen::verify_opening(
+            group,
+            &c,
+            &s1,
+            &s2,
+            &t,
+            &commitment
+        ));
+        let (s1, s2) = pedersen::solve_opening(group, &a, &b, &c, &m, &group.random_scalar());
+        assert!(!pedersen::verify_opening(
+            group,
+            &c,
+            &s1,
+            &s2,
+            &t,
+            &commitment
+        ));
+    }
+
+    #[test]
+    fn test_modp_protocols() {
+        let group = generate_1024bit_group_with_160bit_constants();
+        check_chaum_pedersen(&group);
+        check_schnorr(&group);
+        check_pedersen(&group);
+    }
+
+    #[test]
+    fn test_ristretto_protocols() {
+        let group = pedersen_setup_base_points();
+        check_chaum_pedersen(&group);
+        check_schnorr(&group);
+        check_pedersen(&group);
+    }
+}
@@ -0,0 +1,973 @@
+// Storage of the registered users and of the pending authentication attempts
+// https://www.sqlite.org/atomiccommit.html
+
+use crate::chaum_pedersen::ZKPProtocol;
+use crate::group::PrimeOrderGroup;
+use crate::kdf::KdfParams;
+use crate::pedersen_elliptic_curve::ZKPEllipticCurve;
+use curve25519_dalek::{RistrettoPoint, Scalar};
+use log::warn;
+use num_bigint::BigUint;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+///
+/// Verifiers registered by a user
+///
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserData {
+    pub user: String,
+    pub exponentiation: Option<PublicPair<ZKPProtocol>>,
+    /// named group of the exponentiation pair, absent in the records older than it
+    #[serde(default)]
+    pub exponentiation_group: Option<String>,
+    pub elliptic_curve: Option<PublicPair<ZKPEllipticCurve>>,
+    pub pedersen_commitment: Option<RistrettoPoint>,
+    /// salt of the blinding factor of the commitment
+    #[serde(default)]
+    pub blinding_salt: Vec<u8>,
+    pub salt: Vec<u8>,
+    pub kdf_params: Option<KdfParams>,
+    /// random value replaced with the credential, bound to the proofs of its rotation
+    #[serde(default)]
+    pub rotation_nonce: Vec<u8>,
+    /// time of the last rotation of the credential, the logins challenged before are invalid
+    #[serde(default)]
+    pub rotated_at: Option<SystemTime>,
+}
+
+impl UserData {
+    ///
+    /// The credential was replaced at or after `time`
+    ///
+    pub fn rotated_since(&self, time: SystemTime) -> bool {
+        self.rotated_at.is_some_and(|rotated_at| rotated_at >= time)
+    }
+}
+
+pub type PublicPair<G> = (
+    <G as PrimeOrderGroup>::Element,
+    <G as PrimeOrderGroup>::Element,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthMode {
+    Exponentiation,
+    EllipticCurveChaumPedersen,
+    PedersenOpening,
+}
+
+impl AuthMode {
+    pub const ALL: [AuthMode; 3] = [
+        AuthMode::Exponentiation,
+        AuthMode::EllipticCurveChaumPedersen,
+        AuthMode::PedersenOpening,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuthMode::Exponentiation => "exponentiation",
+            AuthMode::EllipticCurveChaumPedersen => "elliptic-curve-chaum-pedersen",
+            AuthMode::PedersenOpening => "pedersen-opening",
+        }
+    }
+}
+
+impl fmt::Display for AuthMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AuthMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AuthMode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| format!("Unknown authentication mode: {}", s))
+    }
+}
+
+///
+/// One login attempt, keyed by its auth_id: each attempt keeps its own commitment and
+/// challenge so that parallel logins of the same user do not interfere
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthAttempt {
+    pub user: String,
+    pub created_at: SystemTime,
+    pub transcript: Transcript,
+}
+
+///
+/// Commitment sent by the prover and challenge sent back by the server
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Transcript {
+    Exponentiation {
+        r1: BigUint,
+        r2: BigUint,
+        c: BigUint,
+    },
+    EllipticCurveChaumPedersen {
+        r1: RistrettoPoint,
+        r2: RistrettoPoint,
+        c: Scalar,
+    },
+    PedersenOpening {
+        t: RistrettoPoint,
+        c: Scalar,
+    },
+}
+
+impl AuthAttempt {
+    pub fn new(user: String, created_at: SystemTime, transcript: Transcript) -> Self {
+        AuthAttempt {
+            user,
+            created_at,
+            transcript,
+        }
+    }
+
+    pub fn is_expired(&self, now: SystemTime, ttl: Duration) -> bool {
+        now.duration_since(self.created_at).unwrap_or_default() > ttl
+    }
+
+    pub fn mode(&self) -> AuthMode {
+        match self.transcript {
+            Transcript::Exponentiation { .. } => AuthMode::Exponentiation,
+            Transcript::EllipticCurveChaumPedersen { .. } => AuthMode::EllipticCurveChaumPedersen,
+            Transcript::PedersenOpening { .. } => AuthMode::PedersenOpening,
+        }
+    }
+}
+
+///
+/// Session opened by a successful authentication, keyed by its session_id
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub user: String,
+    /// Mode of the login, None for the sessions stored without it
+    #[serde(default)]
+    pub mode: Option<AuthMode>,
+    /// Time of the challenge of the login, kept when the session is refreshed
+    pub created_at: SystemTime,
+    pub last_seen: SystemTime,
+    /// Subject of the client certificate of the login, informative only
+    #[serde(default)]
+    pub client_subject: Option<String>,
+    /// Fingerprint of the client certificate of the login (`tls::certificate_fingerprint`),
+    /// only that client can refresh or revoke the session
+    #[serde(default)]
+    pub client_fingerprint: Option<String>,
+}
+
+///
+/// A session expires after `idle` without being used or `absolute` after the login
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionTimeouts {
+    pub idle: Duration,
+    pub absolute: Duration,
+}
+
+impl Session {
+    pub fn new(user: String, mode: AuthMode, now: SystemTime) -> Self {
+        Session {
+            user,
+            mode: Some(mode),
+            created_at: now,
+            last_seen: now,
+            client_subject: None,
+            client_fingerprint: None,
+        }
+    }
+
+    pub fn expires_at(&self, timeouts: &SessionTimeouts) -> SystemTime {
+        (self.last_seen + timeouts.idle).min(self.created_at + timeouts.absolute)
+    }
+
+    pub fn is_expired(&self, now: SystemTime, timeouts: &SessionTimeouts) -> bool {
+        now >= self.expires_at(timeouts)
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Sqlite(rusqlite::Error),
+    Encoding(serde_json::Error),
+    InvalidBackend(String),
+    /// a thread panicked while holding the lock of the store
+    Poisoned,
+    /// the store returned without calling the update of `modify_user` or `modify_session`
+    UpdateNotApplied,
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Sqlite(e) => write!(f, "sqlite error: {}", e),
+            StoreError::Encoding(e) => write!(f, "invalid stored record: {}", e),
+            StoreError::InvalidBackend(backend) => write!(
+                f,
+                "unknown store {}, expected memory or sqlite:<path>",
+                backend
+            ),
+            StoreError::Poisoned => write!(f, "the store lock is poisoned"),
+            StoreError::UpdateNotApplied => write!(f, "the store did not apply the update"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+///
+/// Lock of the store, a panic of another thread holding it is reported as an error
+///
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, StoreError> {
+    mutex.lock().map_err(|_| StoreError::Poisoned)
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        StoreError::Sqlite(e)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Encoding(e)
+    }
+}
+
+///
+/// Storage of the users (verifiers), of the attempts (challenges) and of the sessions.
+/// Every method is atomic: an update reads and writes the entry of the user without
+/// any other change in between
+///
+pub trait UserStore: Send + Sync {
+    fn lookup_user(&self, user: &str) -> Result<Option<UserData>, StoreError>;
+
+    ///
+    /// `f` is called once with the entry of the user, None for an unknown user: it
+    /// registers the user by setting Some and deletes it by setting None
+    ///
+    fn update_user(
+        &self,
+        user: &str,
+        f: &mut dyn FnMut(&mut Option<UserData>),
+    ) -> Result<(), StoreError>;
+
+    fn delete_user(&self, user: &str) -> Result<Option<UserData>, StoreError>;
+
+    fn insert_attempt(&self, auth_id: &str, attempt: &AuthAttempt) -> Result<(), StoreError>;
+
+    ///
+    /// Remove the attempt of the auth_id and return it
+    ///
+    fn take_attempt(&self, auth_id: &str) -> Result<Option<AuthAttempt>, StoreError>;
+
+    ///
+    /// Keep the attempts matching `f`, returns how many were removed
+    ///
+    fn retain_attempts(&self, f: &mut dyn FnMut(&AuthAttempt) -> bool)
+        -> Result<usize, StoreError>;
+
+    fn insert_session(&self, session_id: &str, session: &Session) -> Result<(), StoreError>;
+
+    ///
+    /// `f` is called once with the session, None for an unknown session_id: setting
+    /// None revokes it
+    ///
+    fn update_session(
+        &self,
+        session_id: &str,
+        f: &mut dyn FnMut(&mut Option<Session>),
+    ) -> Result<(), StoreError>;
+
+    ///
+    /// Keep the sessions matching `f`, returns how many were revoked. Every session is
+    /// read, this is the expiry sweep
+    ///
+    fn retain_sessions(&self, f: &mut dyn FnMut(&Session) -> bool) -> Result<usize, StoreError>;
+
+    ///
+    /// Revoke every session of the user, returns how many were revoked
+    ///
+    fn remove_user_sessions(&self, user: &str) -> Result<usize, StoreError>;
+}
+
+impl dyn UserStore + '_ {
+    ///
+    /// `update_user` returning the result of `f`
+    ///
+    pub fn modify_user<R>(
+        &self,
+        user: &str,
+        f: impl FnOnce(&mut Option<UserData>) -> R,
+    ) -> Result<R, StoreError> {
+        let mut f = Some(f);
+        let mut result = None;
+        self.update_user(user, &mut |entry| {
+            result = f.take().map(|f| f(entry));
+        })?;
+        result.ok_or(StoreError::UpdateNotApplied)
+    }
+
+    ///
+    /// `update_session` returning the result of `f`
+    ///
+    pub fn modify_session<R>(
+        &self,
+        session_id: &str,
+        f: impl FnOnce(&mut Option<Session>) -> R,
+    ) -> Result<R, StoreError> {
+        let mut f = Some(f);
+        let mut result = None;
+        self.update_session(session_id, &mut |entry| {
+            result = f.take().map(|f| f(entry));
+        })?;
+        result.ok_or(StoreError::UpdateNotApplied)
+    }
+}
+
+///
+/// Users, attempts and sessions kept in memory, lost on restart. Each map is behind its
+/// own lock and no method holds two of them at once
+///
+#[derive(Default)]
+pub struct MemoryUserStore {
+    users: Mutex<HashMap<String, UserData>>,
+    attempts: Mutex<HashMap<String, AuthAttempt>>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl UserStore for MemoryUserStore {
+    fn lookup_user(&self, user: &str) -> Result<Option<UserData>, StoreError> {
+        Ok(lock(&self.users)?.get(user).cloned())
+    }
+
+    fn update_user(
+        &self,
+        user: &str,
+        f: &mut dyn FnMut(&mut Option<UserData>),
+    ) -> Result<(), StoreError> {
+        let users = &mut lock(&self.users)?;
+        update_entry(users, user, f);
+        Ok(())
+    }
+
+    fn delete_user(&self, user: &str) -> Result<Option<UserData>, StoreError> {
+        Ok(lock(&self.users)?.remove(user))
+    }
+
+    fn insert_attempt(&self, auth_id: &str, attempt: &AuthAttempt) -> Result<(), StoreError> {
+        lock(&self.attempts)?.insert(auth_id.to_string(), attempt.clone());
+        Ok(())
+    }
+
+    fn take_attempt(&self, auth_id: &str) -> Result<Option<AuthAttempt>, StoreError> {
+        Ok(lock(&self.attempts)?.remove(auth_id))
+    }
+
+    fn retain_attempts(
+        &self,
+        f: &mut dyn FnMut(&AuthAttempt) -> bool,
+    ) -> Result<usize, StoreError> {
+        let attempts = &mut lock(&self.attempts)?;
+        let before = attempts.len();
+        attempts.retain(|_, attempt| f(attempt));
+        Ok(before - attempts.len())
+    }
+
+    fn insert_session(&self, session_id: &str, session: &Session) -> Result<(), StoreError> {
+        lock(&self.sessions)?.insert(session_id.to_string(), session.clone());
+        Ok(())
+    }
+
+    fn update_session(
+        &self,
+        session_id: &str,
+        f: &mut dyn FnMut(&mut Option<Session>),
+    ) -> Result<(), StoreError> {
+        let sessions = &mut lock(&self.sessions)?;
+        update_entry(sessions, session_id, f);
+        Ok(())
+    }
+
+    fn retain_sessions(&self, f: &mut dyn FnMut(&Session) -> bool) -> Result<usize, StoreError> {
+        let sessions = &mut lock(&self.sessions)?;
+        let before = sessions.len();
+        sessions.retain(|_, session| f(session));
+        Ok(before - sessions.len())
+    }
+
+    fn remove_user_sessions(&self, user: &str) -> Result<usize, StoreError> {
+        self.retain_sessions(&mut |session| session.user != user)
+    }
+}
+
+///
+/// `f` is called with a copy of the entry, which stays in the map until `f` returns and
+/// is then updated in place, inserted or removed
+///
+fn update_entry<V: Clone>(
+    map: &mut HashMap<String, V>,
+    key: &str,
+    f: &mut dyn FnMut(&mut Option<V>),
+) {
+    let mut entry = map.get(key).cloned();
+    f(&mut entry);
+    match (map.get_mut(key), entry) {
+        (Some(value), Some(updated)) => *value = updated,
+        (None, Some(inserted)) => {
+            map.insert(key.to_string(), inserted);
+        }
+        (_, None) => {
+            map.remove(key);
+        }
+    }
+}
+
+///
+/// Users, attempts and sessions kept in an SQLite database, as JSON records. Every write is a
+/// transaction committed to the write-ahead log with a full sync, so a crash leaves
+/// either the previous or the new record
+///
+pub struct SqliteUserStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteUserStore {
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "synchronous", "FULL")?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS users (user TEXT PRIMARY KEY, data TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS attempts (auth_id TEXT PRIMARY KEY, data TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS sessions (
+                 session_id TEXT PRIMARY KEY, user TEXT NOT NULL, data TEXT NOT NULL
+             );",
+        )?;
+        add_session_users(&connection)?;
+        connection.execute_batch("CREATE INDEX IF NOT EXISTS sessions_user ON sessions (user);")?;
+        Ok(SqliteUserStore {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+///
+/// Adds the user column to a sessions table created without it, filled from the records
+///
+fn add_session_users(connection: &Connection) -> Result<(), StoreError> {
+    let has_user: bool = connection.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('sessions') WHERE name = 'user'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_user {
+        // an undecodable record keeps an empty user until the expiry sweep revokes it
+        connection.execute_batch(
+            "BEGIN IMMEDIATE;
+             ALTER TABLE sessions ADD COLUMN user TEXT NOT NULL DEFAULT '';
+             UPDATE sessions SET user = json_extract(data, '$.user')
+                 WHERE json_valid(data) AND json_type(data, '$.user') = 'text';
+             COMMIT;",
+        )?;
+    }
+    Ok(())
+}
+
+impl UserStore for SqliteUserStore {
+    fn lookup_user(&self, user: &str) -> Result<Option<UserData>, StoreError> {
+        let connection = lock(&self.connection)?;
+        let data: Option<String> = connection
+            .query_row(
+                "SELECT data FROM users WHERE user = ?1",
+                params![user],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    fn update_user(
+        &self,
+        user: &str,
+        f: &mut dyn FnMut(&mut Option<UserData>),
+    ) -> Result<(), StoreError> {
+        let connection = &mut lock(&self.connection)?;
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let data: Option<String> = transaction
+            .query_row(
+                "SELECT data FROM users WHERE user = ?1",
+                params![user],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let mut entry = data.map(|data| serde_json::from_str(&data)).transpose()?;
+        f(&mut entry);
+        match entry {
+            Some(user_data) => transaction.execute(
+                "INSERT OR REPLACE INTO users (user, data) VALUES (?1, ?2)",
+                params![user, serde_json::to_string(&user_data)?],
+            )?,
+            None => transaction.execute("DELETE FROM users WHERE user = ?1", params![user])?,
+        };
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn delete_user(&self, user: &str) -> Result<Option<UserData>, StoreError> {
+        let connection = lock(&self.connection)?;
+        let data: Option<String> = connection
+            .query_row(
+                "DELETE FROM users WHERE user = ?1 RETURNING data",
+                params![user],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    fn insert_attempt(&self, auth_id: &str, attempt: &AuthAttempt) -> Result<(), StoreError> {
+        let connection = lock(&self.connection)?;
+        connection.execute(
+            "INSERT OR REPLACE INTO attempts (auth_id, data) VALUES (?1, ?2)",
+            params![auth_id, serde_json::to_string(attempt)?],
+        )?;
+        Ok(())
+    }
+
+    fn take_attempt(&self, auth_id: &str) -> Result<Option<AuthAttempt>, StoreError> {
+        let connection = lock(&self.connection)?;
+        let data: Option<String> = connection
+            .query_row(
+                "DELETE FROM attempts WHERE auth_id = ?1 RETURNING data",
+                params![auth_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    fn retain_attempts(
+        &self,
+        f: &mut dyn FnMut(&AuthAttempt) -> bool,
+    ) -> Result<usize, StoreError> {
+        let connection = &mut lock(&self.connection)?;
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut removed = Vec::new();
+        {
+            let mut statement = transaction.prepare("SELECT auth_id, data FROM attempts")?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                // an attempt that cannot be decoded cannot be answered either, it is removed
+                let keep = match serde_json::from_str(&row.get::<_, String>(1)?) {
+                    Ok(attempt) => f(&attempt),
+                    Err(e) => {
+                        warn!("Removing an undecodable attempt: {}", e);
+                        false
+                    }
+                };
+                if !keep {
+                    removed.push(row.get::<_, String>(0)?);
+                }
+            }
+        }
+        for auth_id in &removed {
+            transaction.execute("DELETE FROM attempts WHERE auth_id = ?1", params![auth_id])?;
+        }
+        transaction.commit()?;
+        Ok(removed.len())
+    }
+    fn insert_session(&self, session_id: &str, session: &Session) -> Result<(), StoreError> {
+        let connection = lock(&self.connection)?;
+        connection.execute(
+            "INSERT OR REPLACE INTO sessions (session_id, user, data) VALUES (?1, ?2, ?3)",
+            params![session_id, session.user, serde_json::to_string(session)?],
+        )?;
+        Ok(())
+    }
+
+    fn update_session(
+        &self,
+        session_id: &str,
+        f: &mut dyn FnMut(&mut Option<Session>),
+    ) -> Result<(), StoreError> {
+        let connection = &mut lock(&self.connection)?;
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let data: Option<String> = transaction
+            .query_row(
+                "SELECT data FROM sessions WHERE session_id = ?1",
+                params![session_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let mut entry = data.map(|data| serde_json::from_str(&data)).transpose()?;
+        f(&mut entry);
+        match entry {
+            Some(session) => transaction.execute(
+                "INSERT OR REPLACE INTO sessions (session_id, user, data) VALUES (?1, ?2, ?3)",
+                params![session_id, session.user, serde_json::to_string(&session)?],
+            )?,
+            None => transaction.execute(
+                "DELETE FROM sessions WHERE session_id = ?1",
+                params![session_id],
+            )?,
+        };
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn retain_sessions(&self, f: &mut dyn FnMut(&Session) -> bool) -> Result<usize, StoreError> {
+        let connection = &mut lock(&self.connection)?;
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut removed = Vec::new();
+        {
+            let mut statement = transaction.prepare("SELECT session_id, data FROM sessions")?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                // a session that cannot be decoded cannot be validated either, it is revoked
+                let keep = match serde_json::from_str(&row.get::<_, String>(1)?) {
+                    Ok(session) => f(&session),
+                    Err(e) => {
+                        warn!("Revoking an undecodable session: {}", e);
+                        false
+                    }
+                };
+                if !keep {
+                    removed.push(row.get::<_, String>(0)?);
+                }
+            }
+        }
+        for session_id in &removed {
+            transaction.execute(
+                "DELETE FROM sessions WHERE session_id = ?1",
+                params![session_id],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(removed.len())
+    }
+
+    fn remove_user_sessions(&self, user: &str) -> Result<usize, StoreError> {
+        let connection = lock(&self.connection)?;
+        Ok(connection.execute("DELETE FROM sessions WHERE user = ?1", params![user])?)
+    }
+}
+
+///
+/// Store selected by the server configuration: `memory` or `sqlite:<path>`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreBackend {
+    Memory,
+    Sqlite(PathBuf),
+}
+
+impl StoreBackend {
+    pub fn open(&self) -> Result<Arc<dyn UserStore>, StoreError> {
+        Ok(match self {
+            StoreBackend::Memory => Arc::new(MemoryUserStore::default()),
+            StoreBackend::Sqlite(path) => Arc::new(SqliteUserStore::open(path)?),
+        })
+    }
+}
+
+impl FromStr for StoreBackend {
+    type Err = StoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "memory" => Ok(StoreBackend::Memory),
+            Some(("sqlite", path)) if !path.is_empty() => Ok(StoreBackend::Sqlite(path.into())),
+            _ => Err(StoreError::InvalidBackend(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for StoreBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreBackend::Memory => write!(f, "memory"),
+            StoreBackend::Sqlite(path) => write!(f, "sqlite:{}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pedersen_elliptic_curve::{generate_random_scalar, pedersen_setup_base_points};
+
+    fn user_data(user: &str) -> UserData {
+        let ec = pedersen_setup_base_points();
+        let x = generate_random_scalar();
+        UserData {
+            user: user.to_string(),
+            exponentiation: Some((BigUint::from(4u32), BigUint::from(9u32))),
+            exponentiation_group: Some("ffdhe2048".to_string()),
+            elliptic_curve: Some(ec.compute_public_pair(&x)),
+            pedersen_commitment: Some(ec.pedersen_commit(x, generate_random_scalar())),
+            blinding_salt: vec![6; 16],
+            salt: vec![7; 16],
+            kdf_params: Some(KdfParams::INSECURE_FAST),
+            rotation_nonce: vec![8; 16],
+            rotated_at: None,
+        }
+    }
+
+    fn attempt(user: &str, created_at: SystemTime) -> AuthAttempt {
+        let ec = pedersen_setup_base_points();
+        AuthAttempt::new(
+            user.to_string(),
+            created_at,
+            Transcript::PedersenOpening {
+                t: ec.g,
+                c: generate_random_scalar(),
+            },
+        )
+    }
+
+    fn check_store(store: &dyn UserStore) {
+        assert_eq!(store.lookup_user("alice").unwrap(), None);
+        let alice = user_data("alice");
+        store
+            .modify_user("alice", |entry| *entry = Some(alice.clone()))
+            .unwrap();
+        assert_eq!(store.lookup_user("alice").unwrap(), Some(alice.clone()));
+
+        let salt = store
+            .modify_user("alice", |entry| {
+                let user_data = entry.as_mut().unwrap();
+                user_data.salt = vec![8; 16];
+                user_data.salt.clone()
+            })
+            .unwrap();
+        assert_eq!(store.lookup_user("alice").unwrap().unwrap().salt, salt);
+        assert!(store.delete_user("alice").unwrap().is_some());
+        assert_eq!(store.lookup_user("alice").unwrap(), None);
+        assert_eq!(store.delete_user("alice").unwrap(), None);
+
+        let old = attempt("alice", SystemTime::UNIX_EPOCH);
+        let new = attempt("alice", SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+        store.insert_attempt("old", &old).unwrap();
+        store.insert_attempt("new", &new).unwrap();
+        let removed = store
+            .retain_attempts(&mut |attempt| attempt.created_at > SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert_eq!(store.take_attempt("old").unwrap(), None);
+        assert_eq!(store.take_attempt("new").unwrap(), Some(new));
+        assert_eq!(store.take_attempt("new").unwrap(), None);
+
+        let session = Session::new(
+            "alice".to_string(),
+            AuthMode::Exponentiation,
+            SystemTime::UNIX_EPOCH,
+        );
+        store.insert_session("s1", &session).unwrap();
+        store
+            .insert_session(
+                "s2",
+                &Session::new(
+                    "bob".to_string(),
+                    AuthMode::PedersenOpening,
+                    SystemTime::UNIX_EPOCH,
+                ),
+            )
+            .unwrap();
+        let later = SystemTime::UNIX_EPOCH + Duration::from_secs(5);
+        let touched = store
+            .modify_session("s1", |entry| {
+                entry.as_mut().map(|session| {
+                    session.last_seen = later;
+                    session.clone()
+                })
+            })
+            .unwrap();
+        assert_eq!(touched.unwrap().last_seen, later);
+        assert_eq!(store.remove_user_sessions("bob").unwrap(), 1);
+        assert_eq!(store.remove_user_sessions("bob").unwrap(), 0);
+        assert_eq!(
+            store.modify_session("s2", |entry| entry.take()).unwrap(),
+            None
+        );
+        let revoked = store.modify_session("s1", |entry| entry.take()).unwrap();
+        assert_eq!(revoked.unwrap().user, "alice");
+        assert_eq!(
+            store.modify_session("s1", |entry| entry.take()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_session_expiry() {
+        let timeouts = SessionTimeouts {
+            idle: Duration::from_secs(10),
+            absolute: Duration::from_secs(25),
+        };
+        let login = SystemTime::UNIX_EPOCH;
+        let mut session = Session::new("alice".to_string(), AuthMode::Exponentiation, login);
+        assert_eq!(
+            session.expires_at(&timeouts),
+            login + Duration::from_secs(10)
+        );
+        assert!(session.is_expired(login + Duration::from_secs(10), &timeouts));
+
+        // the idle timeout restarts when the session is used, not the absolute one
+        session.last_seen = login + Duration::from_secs(9);
+        assert!(!session.is_expired(login + Duration::from_secs(18), &timeouts));
+        session.last_seen = login + Duration::from_secs(18);
+        assert_eq!(
+            session.expires_at(&timeouts),
+            login + Duration::from_secs(25)
+        );
+    }
+
+    #[test]
+    fn test_session_without_mode() {
+        let session = Session::new(
+            "alice".to_string(),
+            AuthMode::PedersenOpening,
+            SystemTime::UNIX_EPOCH,
+        );
+        let mut record = serde_json::to_value(&session).unwrap();
+        record.as_object_mut().unwrap().remove("mode");
+        let decoded: Session = serde_json::from_value(record).unwrap();
+        assert_eq!(decoded.mode, None);
+        assert_eq!(decoded.user, session.user);
+    }
+
+    #[test]
+    fn test_memory_store() {
+        check_store(&MemoryUserStore::default());
+
+        // a panic during an update leaves the entry and poisons the store, which then
+        // returns errors instead of panicking
+        let store = MemoryUserStore::default();
+        let alice = user_data("alice");
+        store
+            .update_user("alice", &mut |entry| *entry = Some(alice.clone()))
+            .unwrap();
+        let panicked = std::panic::catch_unwind(|| {
+            store.update_user("alice", &mut |_| panic!("update failed"))
+        });
+        assert!(panicked.is_err());
+        assert!(matches!(
+            store.lookup_user("alice"),
+            Err(StoreError::Poisoned)
+        ));
+        let users = store.users.into_inner().unwrap_err().into_inner();
+        assert_eq!(users.get("alice"), Some(&alice));
+    }
+
+    #[test]
+    fn test_sqlite_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.db");
+        check_store(&SqliteUserStore::open(&path).unwrap());
+
+        // the records survive a restart
+        let alice = user_data("alice");
+        let store = SqliteUserStore::open(&path).unwrap();
+        store
+            .update_user("alice", &mut |entry| *entry = Some(alice.clone()))
+            .unwrap();
+        store
+            .insert_attempt("id", &attempt("alice", SystemTime::UNIX_EPOCH))
+            .unwrap();
+        drop(store);
+        let store = SqliteUserStore::open(&path).unwrap();
+        assert_eq!(store.lookup_user("alice").unwrap(), Some(alice));
+        assert!(store.take_attempt("id").unwrap().is_some());
+
+        // the undecodable records are removed by the sweeps instead of stopping them
+        store
+            .insert_attempt("id", &attempt("alice", SystemTime::UNIX_EPOCH))
+            .unwrap();
+        store
+            .insert_session(
+                "s1",
+                &Session::new(
+                    "alice".to_string(),
+                    AuthMode::Exponentiation,
+                    SystemTime::UNIX_EPOCH,
+                ),
+            )
+            .unwrap();
+        lock(&store.connection)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO attempts (auth_id, data) VALUES ('bad', '{}');
+                 INSERT INTO sessions (session_id, user, data) VALUES ('bad', '', 'not json');",
+            )
+            .unwrap();
+        assert_eq!(store.retain_attempts(&mut |_| true).unwrap(), 1);
+        assert_eq!(store.retain_sessions(&mut |_| true).unwrap(), 1);
+        assert!(store.take_attempt("id").unwrap().is_some());
+        assert!(store.take_attempt("bad").unwrap().is_none());
+        let store: &dyn UserStore = &store;
+        assert!(store.modify_session("s1", |entry| entry.is_some()).unwrap());
+    }
+
+    #[test]
+    fn test_sqlite_sessions_without_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.db");
+        let session = Session::new(
+            "alice".to_string(),
+            AuthMode::Exponentiation,
+            SystemTime::UNIX_EPOCH,
+        );
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE sessions (session_id TEXT PRIMARY KEY, data TEXT NOT NULL);
+                 INSERT INTO sessions (session_id, data) VALUES ('bad', 'not json');",
+            )
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO sessions (session_id, data) VALUES ('s1', ?1)",
+                params![serde_json::to_string(&session).unwrap()],
+            )
+            .unwrap();
+        drop(connection);
+
+        // the sessions stored before the user column are revoked with their user
+        let store = SqliteUserStore::open(&path).unwrap();
+        assert_eq!(store.remove_user_sessions("alice").unwrap(), 1);
+        assert_eq!(store.retain_sessions(&mut |_| true).unwrap(), 1);
+        drop(store);
+        SqliteUserStore::open(&path).unwrap();
+    }
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!(
+            "memory".parse::<StoreBackend>().unwrap(),
+            StoreBackend::Memory
+        );
+        assert_eq!(
+            "sqlite:/var/lib/zkp/users.db"
+                .parse::<StoreBackend>()
+                .unwrap(),
+            StoreBackend::Sqlite("/var/lib/zkp/users.db".into())
+        );
+        assert!("sqlite:".parse::<StoreBackend>().is_err());
+        assert!("redis".parse::<StoreBackend>().is_err());
+    }
+}
@@ -0,0 +1,248 @@
+// TLS of the gRPC server and client from PEM files, with optional client certificates
+// The verified client certificate is given to the handlers by `peer_certificate`
+
+use crate::config::TlsConfig;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+#[derive(Debug)]
+pub enum TlsError {
+    Read(PathBuf, std::io::Error),
+    Incomplete(&'static str),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::Read(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+            TlsError::Incomplete(reason) => write!(f, "incomplete TLS configuration: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TlsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TlsError::Read(_, e) => Some(e),
+            TlsError::Incomplete(_) => None,
+        }
+    }
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, TlsError> {
+    std::fs::read(path).map_err(|e| TlsError::Read(path.into(), e))
+}
+
+///
+/// TLS of the server, `None` when no certificate is configured. With a client CA the
+/// clients must present a certificate issued by it
+///
+pub fn server_tls_config(config: &TlsConfig) -> Result<Option<ServerTlsConfig>, TlsError> {
+    let (cert, key) = match (&config.cert, &config.key) {
+        (Some(cert), Some(key)) => (cert, key),
+        (None, None) if config.client_ca.is_none() => return Ok(None),
+        _ => return Err(TlsError::Incomplete("tls.cert and tls.key are required")),
+    };
+    let mut tls =
+        ServerTlsConfig::new().identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+    if let Some(client_ca) = &config.client_ca {
+        tls = tls.client_ca_root(Certificate::from_pem(read_pem(client_ca)?));
+    }
+    Ok(Some(tls))
+}
+
+///
+/// TLS of a client: the server certificate is verified against `ca_cert` only, and the
+/// client certificate and key are presented when the server requires them
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientTls {
+    pub ca_cert: PathBuf,
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    /// Name expected in the server certificate, the host of the URL by default
+    pub domain: Option<String>,
+}
+
+impl ClientTls {
+    pub fn new(ca_cert: impl Into<PathBuf>) -> Self {
+        ClientTls {
+            ca_cert: ca_cert.into(),
+            cert: None,
+            key: None,
+            domain: None,
+        }
+    }
+
+    pub fn with_identity(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.cert = Some(cert.into());
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    pub fn client_tls_config(&self) -> Result<ClientTlsConfig, TlsError> {
+        let mut tls =
+            ClientTlsConfig::new().ca_certificate(Certificate::from_pem(read_pem(&self.ca_cert)?));
+        match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => {
+                tls = tls.identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+            }
+            (None, None) => {}
+            _ => {
+                return Err(TlsError::Incomplete(
+                    "the client certificate and key go together",
+                ))
+            }
+        }
+        if let Some(domain) = &self.domain {
+            tls = tls.domain_name(domain);
+        }
+        Ok(tls)
+    }
+}
+
+///
+/// Client certificate verified by the TLS handshake: the sessions are bound to its
+/// fingerprint, the subject is only informative as other certificates can share it
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificate {
+    /// `certificate_fingerprint` of the DER certificate
+    pub fingerprint: String,
+    /// Subject such as `CN=alice-laptop, O=Example`
+    pub subject: Option<String>,
+}
+
+impl PeerCertificate {
+    pub fn from_der(der: &[u8]) -> Self {
+        PeerCertificate {
+            fingerprint: certificate_fingerprint(der),
+            subject: certificate_subject(der),
+        }
+    }
+}
+
+///
+/// Client certificate of the request, `None` without mutual TLS
+///
+pub fn peer_certificate<T>(request: &tonic::Request<T>) -> Option<PeerCertificate> {
+    let certs = request.peer_certs()?;
+    Some(PeerCertificate::from_der(certs.first()?.get_ref()))
+}
+
+///
+/// `peer_certificate` of a request seen by a tower layer, before tonic decodes it
+///
+pub fn http_peer_certificate<B>(request: &http::Request<B>) -> Option<PeerCertificate> {
+    let certs = request
+        .extensions()
+        .get::<TlsConnectInfo<TcpConnectInfo>>()?
+        .peer_certs()?;
+    Some(PeerCertificate::from_der(certs.first()?.get_ref()))
+}
+
+///
+/// Subject of the client certificate verified by the TLS handshake of the request, such
+/// as `CN=alice-laptop, O=Example`, `None` without mutual TLS
+///
+pub fn peer_subject<T>(request: &tonic::Request<T>) -> Option<String> {
+    peer_certificate(request)?.subject
+}
+
+///
+/// base64url SHA-256 of a DER certificate, the `x5t#S256` thumbprint of RFC 8705
+///
+pub fn certificate_fingerprint(der: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(der))
+}
+
+///
+/// Subject of a DER certificate in the RFC 4514 string form
+///
+pub fn certificate_subject(der: &[u8]) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    Some(cert.subject().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{Certificate as GeneratedCertificate, CertificateParams, DnType};
+
+    #[test]
+    fn test_certificate_subject() {
+        let mut params = CertificateParams::new(vec!["localhost".to_string()]);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "alice-laptop");
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "Example");
+        let cert = GeneratedCertificate::from_params(params).unwrap();
+        let subject = certificate_subject(&cert.serialize_der().unwrap()).unwrap();
+        assert!(subject.contains("CN=alice-laptop"), "{}", subject);
+        assert!(subject.contains("O=Example"), "{}", subject);
+        assert_eq!(certificate_subject(b"not a certificate"), None);
+
+        // the fingerprint tells apart two certificates of the same subject
+        let der = cert.serialize_der().unwrap();
+        let peer = PeerCertificate::from_der(&der);
+        assert_eq!(peer.fingerprint.len(), 43);
+        assert_eq!(peer.subject, Some(subject));
+        let mut params = CertificateParams::new(vec!["localhost".to_string()]);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "alice-laptop");
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "Example");
+        let other = GeneratedCertificate::from_params(params).unwrap();
+        let other = PeerCertificate::from_der(&other.serialize_der().unwrap());
+        assert_eq!(other.subject, peer.subject);
+        assert_ne!(other.fingerprint, peer.fingerprint);
+    }
+
+    #[test]
+    fn test_incomplete_config() {
+        assert!(server_tls_config(&TlsConfig::default()).unwrap().is_none());
+        let config = TlsConfig {
+            client_ca: Some("ca.pem".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            server_tls_config(&config),
+            Err(TlsError::Incomplete(_))
+        ));
+        let config = TlsConfig {
+            cert: Some("/nonexistent/server.pem".into()),
+            key: Some("/nonexistent/server.key".into()),
+            client_ca: None,
+        };
+        assert!(matches!(
+            server_tls_config(&config),
+            Err(TlsError::Read(..))
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let ca = dir.path().join("ca.pem");
+        std::fs::write(&ca, "").unwrap();
+        let client = ClientTls {
+            cert: Some("client.pem".into()),
+            ..ClientTls::new(&ca)
+        };
+        assert!(matches!(
+            client.client_tls_config(),
+            Err(TlsError::Incomplete(_))
+        ));
+    }
+}
@@ -0,0 +1,416 @@
+// Self-contained session tokens signed with a Schnorr signature over Ristretto
+// Any service knowing the public key of the server can verify them without the store
+
+use crate::group::PrimeOrderGroup;
+use crate::pedersen_elliptic_curve::pedersen_setup_base_points;
+use crate::sigma::schnorr;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use curve25519_dalek::{RistrettoPoint, Scalar};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// Version prefix of the signed tokens, `zkp1.<kid>.<claims>.<signature>`
+pub const TOKEN_PREFIX: &str = "zkp1";
+/// Domain separation tag of the token signatures
+const TOKEN_DOMAIN: &[u8] = b"zkp-protocol-ex/token/v1";
+
+///
+/// Content of a token: the user (`sub`), the authentication method, the issue and expiry
+/// times in Unix seconds, the scopes granted, a unique id and the client certificate of
+/// the login if any
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    pub sub: String,
+    /// `AuthMode::name`, `unknown` for the sessions stored without their mode
+    pub method: String,
+    pub iat: u64,
+    pub exp: u64,
+    pub scopes: Vec<String>,
+    pub jti: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cnf: Option<Confirmation>,
+}
+
+///
+/// Confirmation claim of RFC 8705: a service accepts the token only from the client
+/// presenting the certificate of this fingerprint (`tls::certificate_fingerprint`)
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Confirmation {
+    #[serde(rename = "x5t#S256")]
+    pub x5t_s256: String,
+}
+
+impl TokenClaims {
+    pub fn expires_at(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.exp)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    Malformed,
+    InvalidKey(String),
+    UnknownKey(String),
+    BadSignature,
+    Expired,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::Malformed => write!(f, "malformed token"),
+            TokenError::InvalidKey(reason) => write!(f, "invalid token key: {}", reason),
+            TokenError::UnknownKey(kid) => write!(f, "token signed with the unknown key {}", kid),
+            TokenError::BadSignature => write!(f, "invalid token signature"),
+            TokenError::Expired => write!(f, "expired token"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+fn check_kid(kid: &str) -> Result<(), TokenError> {
+    if kid.is_empty() || kid.contains(['.', ':']) {
+        return Err(TokenError::InvalidKey(format!(
+            "the key id {:?} must be non-empty without '.' nor ':'",
+            kid
+        )));
+    }
+    Ok(())
+}
+
+///
+/// Secret key of the server, `<kid>:<hex of the scalar>` in text form (`to_secret_string`
+/// and `FromStr`). The key id is written in the tokens so that the verifiers can hold the
+/// keys of several rotations. `Display` and `Debug` do not print the secret
+///
+#[derive(Clone)]
+pub struct SigningKey {
+    kid: String,
+    secret: Scalar,
+}
+
+impl SigningKey {
+    pub fn generate(kid: &str) -> Result<Self, TokenError> {
+        check_kid(kid)?;
+        Ok(SigningKey {
+            kid: kid.to_string(),
+            secret: pedersen_setup_base_points().random_scalar(),
+        })
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey {
+            kid: self.kid.clone(),
+            public: schnorr::public_key(&pedersen_setup_base_points(), &self.secret),
+        }
+    }
+
+    ///
+    /// `<kid>:<hex of the scalar>`, the form parsed by `FromStr`
+    ///
+    pub fn to_secret_string(&self) -> String {
+        format!("{}:{}", self.kid, hex::encode(self.secret.to_bytes()))
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("kid", &self.kid)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:<redacted>", self.kid)
+    }
+}
+
+impl FromStr for SigningKey {
+    type Err = TokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kid, secret) = s
+            .split_once(':')
+            .ok_or_else(|| TokenError::InvalidKey("expected <kid>:<hex>".to_string()))?;
+        check_kid(kid)?;
+        let secret = hex::decode(secret)
+            .ok()
+            .and_then(|bytes| pedersen_setup_base_points().decode_scalar(&bytes).ok())
+            .ok_or_else(|| TokenError::InvalidKey("the secret is not a scalar".to_string()))?;
+        Ok(SigningKey {
+            kid: kid.to_string(),
+            secret,
+        })
+    }
+}
+
+///
+/// Public key of the server, `<kid>:<hex of the compressed point>` in text form
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingKey {
+    pub kid: String,
+    pub public: RistrettoPoint,
+}
+
+impl fmt::Display for VerifyingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            self.kid,
+            hex::encode(self.public.compress().as_bytes())
+        )
+    }
+}
+
+impl FromStr for VerifyingKey {
+    type Err = TokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kid, public) = s
+            .split_once(':')
+            .ok_or_else(|| TokenError::InvalidKey("expected <kid>:<hex>".to_string()))?;
+        check_kid(kid)?;
+        let public = hex::decode(public)
+            .ok()
+            .and_then(|bytes| pedersen_setup_base_points().decode_element(&bytes).ok())
+            .ok_or_else(|| TokenError::InvalidKey("the public key is not a point".to_string()))?;
+        Ok(VerifyingKey {
+            kid: kid.to_string(),
+            public,
+        })
+    }
+}
+
+///
+/// Signs the tokens with the current key of the server
+///
+pub struct TokenIssuer {
+    key: SigningKey,
+}
+
+impl TokenIssuer {
+    pub fn new(key: SigningKey) -> Self {
+        TokenIssuer { key }
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    ///
+    /// `zkp1.<kid>.<claims>.<signature>`: the claims are JSON and the signature c || s,
+    /// both base64url, the signed message is everything before the last dot
+    ///
+    pub fn issue(&self, claims: &TokenClaims) -> String {
+        let claims = serde_json::to_vec(claims).expect("claims are serializable");
+        let message = format!(
+            "{}.{}.{}",
+            TOKEN_PREFIX,
+            self.key.kid,
+            URL_SAFE_NO_PAD.encode(claims)
+        );
+        let (c, s) = schnorr::sign(
+            &pedersen_setup_base_points(),
+            TOKEN_DOMAIN,
+            &self.key.secret,
+            message.as_bytes(),
+        );
+        let signature = [c.to_bytes(), s.to_bytes()].concat();
+        format!("{}.{}", message, URL_SAFE_NO_PAD.encode(signature))
+    }
+}
+
+///
+/// Verifies the tokens against the public keys of the server, indexed by key id: the key
+/// of the previous rotation stays until its last tokens have expired
+///
+#[derive(Default)]
+pub struct TokenVerifier {
+    keys: HashMap<String, RistrettoPoint>,
+}
+
+impl TokenVerifier {
+    pub fn new(keys: impl IntoIterator<Item = VerifyingKey>) -> Self {
+        let mut verifier = TokenVerifier::default();
+        for key in keys {
+            verifier.add_key(key);
+        }
+        verifier
+    }
+
+    pub fn add_key(&mut self, key: VerifyingKey) {
+        self.keys.insert(key.kid, key.public);
+    }
+
+    pub fn remove_key(&mut self, kid: &str) {
+        self.keys.remove(kid);
+    }
+
+    ///
+    /// Claims of a token signed by one of the keys and not expired at `now`
+    ///
+    pub fn verify(&self, token: &str, now: SystemTime) -> Result<TokenClaims, TokenError> {
+        let (message, signature) = token.rsplit_once('.').ok_or(TokenError::Malformed)?;
+        let mut parts = message.split('.');
+        let (Some(TOKEN_PREFIX), Some(kid), Some(claims), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(TokenError::Malformed);
+        };
+        let public = self
+            .keys
+            .get(kid)
+            .ok_or_else(|| TokenError::UnknownKey(kid.to_string()))?;
+        let group = pedersen_setup_base_points();
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| TokenError::Malformed)?;
+        if signature.len() != 64 {
+            return Err(TokenError::Malformed);
+        }
+        let (c, s) = match (
+            group.decode_scalar(&signature[..32]),
+            group.decode_scalar(&signature[32..]),
+        ) {
+            (Ok(c), Ok(s)) => (c, s),
+            _ => return Err(TokenError::Malformed),
+        };
+        if !schnorr::verify_signature(&group, TOKEN_DOMAIN, &c, &s, public, message.as_bytes()) {
+            return Err(TokenError::BadSignature);
+        }
+        let claims = URL_SAFE_NO_PAD
+            .decode(claims)
+            .map_err(|_| TokenError::Malformed)?;
+        let claims: TokenClaims =
+            serde_json::from_slice(&claims).map_err(|_| TokenError::Malformed)?;
+        if now >= claims.expires_at() {
+            return Err(TokenError::Expired);
+        }
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(exp: u64) -> TokenClaims {
+        TokenClaims {
+            sub: "alice".to_string(),
+            method: "exponentiation".to_string(),
+            iat: 100,
+            exp,
+            scopes: vec!["read".to_string()],
+            jti: "id".to_string(),
+            cnf: None,
+        }
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_issue_verify() {
+        let issuer = TokenIssuer::new(SigningKey::generate("2026-10").unwrap());
+        let verifier = TokenVerifier::new([issuer.verifying_key()]);
+        let token = issuer.issue(&claims(200));
+        assert!(token.starts_with("zkp1.2026-10."));
+        assert_eq!(verifier.verify(&token, at(150)), Ok(claims(200)));
+        assert_eq!(verifier.verify(&token, at(200)), Err(TokenError::Expired));
+
+        // the claims and the key id are covered by the signature
+        let forged = issuer.issue(&claims(300));
+        let forged_claims = forged.split('.').nth(2).unwrap();
+        let parts: Vec<&str> = token.split('.').collect();
+        let tampered = [parts[0], parts[1], forged_claims, parts[3]].join(".");
+        assert_eq!(
+            verifier.verify(&tampered, at(150)),
+            Err(TokenError::BadSignature)
+        );
+        assert_eq!(
+            verifier.verify("zkp1.2026-10.e30", at(150)),
+            Err(TokenError::Malformed)
+        );
+        let other = TokenVerifier::new([SigningKey::generate("2026-10").unwrap().verifying_key()]);
+        assert_eq!(other.verify(&token, at(150)), Err(TokenError::BadSignature));
+    }
+
+    #[test]
+    fn test_confirmation_claim() {
+        let issuer = TokenIssuer::new(SigningKey::generate("k1").unwrap());
+        let verifier = TokenVerifier::new([issuer.verifying_key()]);
+        let bound = TokenClaims {
+            cnf: Some(Confirmation {
+                x5t_s256: "fingerprint".to_string(),
+            }),
+            ..claims(200)
+        };
+        let token = issuer.issue(&bound);
+        let json = URL_SAFE_NO_PAD
+            .decode(token.split('.').nth(2).unwrap())
+            .unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(
+            json.contains(r#""cnf":{"x5t#S256":"fingerprint"}"#),
+            "{}",
+            json
+        );
+        assert_eq!(verifier.verify(&token, at(150)), Ok(bound));
+
+        // the tokens without a client certificate carry no cnf
+        let token = issuer.issue(&claims(200));
+        let json = URL_SAFE_NO_PAD
+            .decode(token.split('.').nth(2).unwrap())
+            .unwrap();
+        assert!(!String::from_utf8(json).unwrap().contains("cnf"));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = TokenIssuer::new(SigningKey::generate("old").unwrap());
+        let new = TokenIssuer::new(SigningKey::generate("new").unwrap());
+        let mut verifier = TokenVerifier::new([old.verifying_key(), new.verifying_key()]);
+        let old_token = old.issue(&claims(200));
+        let new_token = new.issue(&claims(200));
+        assert!(verifier.verify(&old_token, at(150)).is_ok());
+        assert!(verifier.verify(&new_token, at(150)).is_ok());
+        verifier.remove_key("old");
+        assert_eq!(
+            verifier.verify(&old_token, at(150)),
+            Err(TokenError::UnknownKey("old".to_string()))
+        );
+        assert!(verifier.verify(&new_token, at(150)).is_ok());
+    }
+
+    #[test]
+    fn test_key_encoding() {
+        let key = SigningKey::generate("k1").unwrap();
+        let decoded: SigningKey = key.to_secret_string().parse().unwrap();
+        assert_eq!(decoded.verifying_key(), key.verifying_key());
+        let public = key.verifying_key();
+        assert_eq!(public.to_string().parse::<VerifyingKey>(), Ok(public));
+        assert!(SigningKey::generate("a.b").is_err());
+        assert!("k1".parse::<VerifyingKey>().is_err());
+        assert!("k1:00".parse::<SigningKey>().is_err());
+        let secret = hex::encode(key.secret.to_bytes());
+        assert!(!format!("{:?}", key).contains(&secret));
+        assert_eq!(key.to_string(), "k1:<redacted>");
+        assert_eq!(key.to_secret_string(), format!("k1:{}", secret));
+    }
+}
@@ -0,0 +1,116 @@
+// End-to-end test of the client command line against a server process: the passwords are
+// read from the standard input and the results parsed from the --json output
+
+mod common;
+
+use common::ServerProcess;
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const CLIENT_ENV_VARS: [&str; 10] = [
+    "ZKP_SERVER",
+    "ZKP_USER",
+    "ZKP_MODE",
+    "ZKP_GROUP",
+    "ZKP_SESSION",
+    "ZKP_TLS_CA_CERT",
+    "ZKP_TLS_CLIENT_CERT",
+    "ZKP_TLS_CLIENT_KEY",
+    "ZKP_TLS_DOMAIN",
+    "RUST_LOG",
+];
+
+///
+/// Runs the client with the passwords of `stdin`, returns its exit code and JSON output
+///
+fn client(server: &str, args: &[&str], stdin: &str) -> (i32, Value) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_client"));
+    for name in CLIENT_ENV_VARS {
+        command.env_remove(name);
+    }
+    let mut child = command
+        .args(["--server", server, "--json", "--password-stdin"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let value = serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("{}: {}", e, stdout));
+    (output.status.code().unwrap(), value)
+}
+
+fn error_code(value: &Value) -> &str {
+    value["error"]["code"].as_str().unwrap_or_default()
+}
+
+#[test]
+fn test_cli_end_to_end() {
+    let server = ServerProcess::start();
+    let url = server.url();
+    let alice = ["--user", "alice"];
+
+    let (code, value) = client(&url, &[&alice[..], &["register"]].concat(), "hunter2\n");
+    assert_eq!(code, 0, "{}", value);
+    assert_eq!(value["registered"], true);
+    let (code, value) = client(&url, &[&alice[..], &["register"]].concat(), "other\n");
+    assert_eq!((code, error_code(&value)), (6, "AlreadyExists"));
+    assert_eq!(value["error"]["exit_code"], 6);
+
+    // every mode logs in with the same password
+    let mut sessions = Vec::new();
+    for mode in ["exp", "ec", "pedersen"] {
+        let (code, value) = client(
+            &url,
+            &[&alice[..], &["--mode", mode, "login"]].concat(),
+            "hunter2\n",
+        );
+        assert_eq!(code, 0, "{}", value);
+        assert_eq!(value["user"], "alice");
+        sessions.push(value["session_id"].as_str().unwrap().to_string());
+    }
+    let (code, value) = client(&url, &[&alice[..], &["login"]].concat(), "wrong\n");
+    assert_eq!((code, error_code(&value)), (4, "PermissionDenied"));
+    let (code, value) = client(&url, &["--user", "bob", "login"], "hunter2\n");
+    assert_eq!((code, error_code(&value)), (5, "NotFound"));
+    assert_eq!(value["error"]["reason"], "UNKNOWN_USER");
+
+    let (code, value) = client(&url, &["whoami", "--session", &sessions[0]], "");
+    assert_eq!(code, 0, "{}", value);
+    assert_eq!(value["user"], "alice");
+    assert!(value["expires_at"].as_u64().unwrap() > 0);
+    let (code, value) = client(&url, &["logout", "--session", &sessions[0]], "");
+    assert_eq!(code, 0, "{}", value);
+    let (code, value) = client(&url, &["whoami", "--session", &sessions[0]], "");
+    assert_eq!((code, error_code(&value)), (4, "Unauthenticated"));
+
+    // the rotation replaces the password and revokes the other sessions
+    let (code, value) = client(
+        &url,
+        &[&alice[..], &["rotate"]].concat(),
+        "hunter2\ncorrect horse\n",
+    );
+    assert_eq!(code, 0, "{}", value);
+    assert_eq!(value["rotated"], true);
+    let (code, _) = client(&url, &["whoami", "--session", &sessions[1]], "");
+    assert_eq!(code, 4);
+    let (code, _) = client(&url, &[&alice[..], &["login"]].concat(), "hunter2\n");
+    assert_eq!(code, 4);
+    let (code, value) = client(&url, &[&alice[..], &["login"]].concat(), "correct horse\n");
+    assert_eq!(code, 0, "{}", value);
+
+    // usage errors and an unreachable server
+    let (code, value) = client(&url, &[&alice[..], &["login"]].concat(), "");
+    assert_eq!(code, 2, "{}", value);
+    drop(server);
+    let (code, value) = client(&url, &[&alice[..], &["login"]].concat(), "hunter2\n");
+    assert_eq!(code, 3, "{}", value);
+}
@@ -0,0 +1,158 @@
+// Integration test of the public client API against a server process
+
+mod common;
+
+use common::ServerProcess;
+use tonic::Code;
+use zkp_protocol_ex::client::{ClientError, ZkpAuthClient};
+use zkp_protocol_ex::kdf::{KdfError, KdfParams};
+use zkp_protocol_ex::store::AuthMode;
+
+async fn connect(server: &ServerProcess) -> ZkpAuthClient {
+    ZkpAuthClient::connect(server.url())
+        .await
+        .unwrap()
+        .with_kdf_params(KdfParams::INSECURE_FAST)
+}
+
+#[tokio::test]
+async fn test_client_api() {
+    let server = ServerProcess::start();
+    let mut client = connect(&server).await;
+    client.register("alice", "hunter2").await.unwrap();
+    let error = client.register("alice", "other").await.unwrap_err();
+    assert_eq!(error.code(), Some(Code::AlreadyExists));
+
+    for mode in AuthMode::ALL {
+        let session = client.login("alice", "hunter2", mode).await.unwrap();
+        assert_eq!((session.user.as_str(), session.mode), ("alice", mode));
+        assert!(session.bearer().starts_with("Bearer "));
+        let validated = client.validate(&session.id).await.unwrap();
+        assert_eq!(validated.user, "alice");
+        assert_eq!(validated.client_fingerprint, None);
+    }
+    let error = client
+        .login("alice", "wrong", AuthMode::Exponentiation)
+        .await
+        .unwrap_err();
+    assert_eq!(error.reason().as_deref(), Some("VERIFICATION_FAILED"));
+    let error = client
+        .login("bob", "hunter2", AuthMode::EllipticCurveChaumPedersen)
+        .await
+        .unwrap_err();
+    assert_eq!(error.reason().as_deref(), Some("UNKNOWN_USER"));
+
+    // a refresh revokes the previous session_id, a logout the current one
+    let session = client
+        .login("alice", "hunter2", AuthMode::PedersenOpening)
+        .await
+        .unwrap();
+    let refreshed = client.refresh(&session).await.unwrap();
+    assert_ne!(refreshed.id, session.id);
+    assert!(client.validate(&session.id).await.is_err());
+    client.logout(&refreshed.id).await.unwrap();
+    let error = client.validate(&refreshed.id).await.unwrap_err();
+    assert_eq!(error.code(), Some(Code::Unauthenticated));
+
+    // the rotation proves the current password in the exponentiation group and revokes
+    // every session
+    let first = client
+        .login("alice", "hunter2", AuthMode::Exponentiation)
+        .await
+        .unwrap();
+    client
+        .rotate("alice", "hunter2", "correct horse")
+        .await
+        .unwrap();
+    assert!(client.validate(&first.id).await.is_err());
+    assert!(client
+        .login("alice", "hunter2", AuthMode::Exponentiation)
+        .await
+        .is_err());
+    let session = client
+        .login(
+            "alice",
+            "correct horse",
+            AuthMode::EllipticCurveChaumPedersen,
+        )
+        .await
+        .unwrap();
+    let other = client
+        .login("alice", "correct horse", AuthMode::PedersenOpening)
+        .await
+        .unwrap();
+    client.logout_all(&session.id).await.unwrap();
+    assert!(client.validate(&other.id).await.is_err());
+
+    // the server stops, a new connection fails as the ones already established
+    let url = server.url();
+    drop(server);
+    let error = client
+        .login("alice", "correct horse", AuthMode::Exponentiation)
+        .await
+        .unwrap_err();
+    assert!(error.is_unreachable(), "{}", error);
+    let error = ZkpAuthClient::connect(url).await.unwrap_err();
+    assert!(error.is_unreachable(), "{}", error);
+}
+
+#[tokio::test]
+async fn test_rotation_without_exponentiation() {
+    let server =
+        ServerProcess::start_with(&["--modes", "elliptic-curve-chaum-pedersen,pedersen-opening"]);
+    let mut client = connect(&server).await;
+    client.register("dave", "hunter2").await.unwrap();
+
+    // the rotation falls back to a proof over the elliptic curve
+    client
+        .rotate("dave", "hunter2", "correct horse")
+        .await
+        .unwrap();
+    assert!(client
+        .login("dave", "hunter2", AuthMode::PedersenOpening)
+        .await
+        .is_err());
+    let session = client
+        .login(
+            "dave",
+            "correct horse",
+            AuthMode::EllipticCurveChaumPedersen,
+        )
+        .await
+        .unwrap();
+    assert_eq!(client.validate(&session.id).await.unwrap().user, "dave");
+    let error = client.rotate("dave", "hunter2", "other").await.unwrap_err();
+    assert_eq!(error.reason().as_deref(), Some("VERIFICATION_FAILED"));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_kdf_cost_bound() {
+    let server = ServerProcess::start();
+    let mut client = connect(&server).await;
+    client.register("carol", "hunter2").await.unwrap();
+
+    // the parameters of the server above the maximum of the client are not derived
+    let mut bounded = connect(&server).await.with_max_kdf_params(KdfParams {
+        memory_kib: 32,
+        ..KdfParams::INSECURE_FAST
+    });
+    let error = bounded
+        .login("carol", "hunter2", AuthMode::Exponentiation)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Kdf(KdfError::CostTooHigh {
+            name: "memory_kib",
+            value: 64,
+            max: 32,
+        })
+    ));
+
+    // the derivation runs off the single thread of this runtime
+    let session = client
+        .login("carol", "hunter2", AuthMode::PedersenOpening)
+        .await
+        .unwrap();
+    assert_eq!(client.validate(&session.id).await.unwrap().user, "carol");
+}
@@ -0,0 +1,70 @@
+// Server process shared by the integration tests, started on a free port of the loopback
+// interface and killed when dropped
+
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use zkp_protocol_ex::config::ENV_VARS;
+
+/// Time for the server to listen after its start
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct ServerProcess {
+    child: Child,
+    port: u16,
+}
+
+impl ServerProcess {
+    ///
+    /// Server with the defaults and the memory store, configured by the flags only
+    ///
+    pub fn start() -> Self {
+        Self::start_with(&[])
+    }
+
+    ///
+    /// Server with the defaults overridden by the flags of `args`
+    ///
+    pub fn start_with(args: &[&str]) -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut command = Command::new(env!("CARGO_BIN_EXE_server"));
+        for (name, _) in ENV_VARS {
+            command.env_remove(name);
+        }
+        let child = command
+            .args(["--port", &port.to_string(), "--log-level", "warn"])
+            .args(args)
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let mut server = ServerProcess { child, port };
+        let started = Instant::now();
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            if let Some(status) = server.child.try_wait().unwrap() {
+                panic!("the server exited with {}", status);
+            }
+            assert!(
+                started.elapsed() < STARTUP_TIMEOUT,
+                "the server is not listening"
+            );
+            sleep(Duration::from_millis(50));
+        }
+        server
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}